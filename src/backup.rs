@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::Path;
+
+use crate::utils::{get_last, write_atomic};
+
+pub const BACKUPS_TO_KEEP: usize = 5;
+const BACKUP_EXT: &str = ".bak.";
+
+fn split_path(filename: &str) -> (String, String) { // (folder, name)
+    let (folder, name) = get_last(filename, '/');
+    let folder = if folder.is_empty() && !filename.starts_with('/') {
+        String::from(".")
+    }
+    else if folder.is_empty() { // file in root. who does that
+        String::from("/")
+    }
+    else {
+        folder.to_string()
+    };
+    (folder, name.to_string())
+}
+
+pub fn list_backups(filename: &str) -> Vec<String> { // newest first
+    let (folder, name) = split_path(filename);
+    let prefix = format!("{}{}", name, BACKUP_EXT);
+
+    let mut ret: Vec<String> = match fs::read_dir(&folder) {
+        Ok(rd) => rd.filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|n| n.starts_with(&prefix))
+            .map(|n| format!("{}/{}", folder, n))
+            .collect(),
+        Err(_) => Vec::new()
+    };
+    ret.sort(); // timestamps sort properly as strings
+    ret.reverse();
+    ret
+}
+
+pub fn backup_file(filename: &str, keep: usize) -> Result<Option<String>, String> { // copies the file to a timestamped backup next to it and removes old ones
+    if !Path::new(filename).exists() { // nothing to back up
+        return Ok(None)
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S%.3f").to_string();
+    let backup_name = format!("{}{}{}", filename, BACKUP_EXT, stamp);
+    match fs::read(filename) { // bytes, since sqlite saves aren't text
+        Ok(s) => write_atomic(&backup_name, &s)?,
+        Err(_) => return Err(format!("failed to read {} for backup", filename))
+    }
+
+    for old in list_backups(filename).iter().skip(keep) { // rotate
+        let _ = fs::remove_file(old); // not the end of the world if this fails
+    }
+    Ok(Some(backup_name))
+}
+
+pub fn restore_backup(filename: &str, backup: &str) -> Result<(), String> { // backs up the current file first so this can be undone too
+    let contents = match fs::read(backup) {
+        Ok(s) => s,
+        Err(_) => return Err(format!("failed to read backup {}", backup))
+    };
+    backup_file(filename, BACKUPS_TO_KEEP + 1)?; // +1 so the backup being restored doesnt get rotated out from under us
+    write_atomic(filename, &contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> String { // backups are found by listing the folder, so each test gets its own
+        let dir = std::env::temp_dir().join(format!("gw_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    fn back_up(path: &str, contents: &str, keep: usize) -> String {
+        fs::write(path, contents).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2)); // names are timestamped to the millisecond
+        backup_file(path, keep).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = temp_dir("backup_rotation");
+        let path = format!("{}/save.json", dir);
+        assert_eq!(backup_file(&path, BACKUPS_TO_KEEP), Ok(None)); // nothing there yet
+        let made: Vec<String> = (0..BACKUPS_TO_KEEP + 2).map(|i| back_up(&path, &i.to_string(), BACKUPS_TO_KEEP)).collect();
+        let kept = list_backups(&path);
+        let newest = fs::read_to_string(&kept[0]).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(kept.len(), BACKUPS_TO_KEEP);
+        assert_eq!(kept, made.iter().rev().take(BACKUPS_TO_KEEP).cloned().collect::<Vec<String>>()); // the oldest two went
+        assert_eq!(newest, (BACKUPS_TO_KEEP + 1).to_string());
+    }
+
+    #[test]
+    fn test_retention_count() {
+        let dir = temp_dir("backup_retention");
+        let path = format!("{}/save.json", dir);
+        for i in 0..4 {
+            back_up(&path, &i.to_string(), 2);
+        }
+        let kept = list_backups(&path).len();
+        back_up(&path, "4", 0);
+        let none = list_backups(&path).len();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!((kept, none), (2, 0));
+    }
+
+    #[test]
+    fn test_restore() {
+        let dir = temp_dir("backup_restore");
+        let path = format!("{}/save.json", dir);
+        let old = back_up(&path, "old", BACKUPS_TO_KEEP);
+        fs::write(&path, "new").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        restore_backup(&path, &old).unwrap();
+        let restored = fs::read_to_string(&path).unwrap();
+        let kept = list_backups(&path);
+        let undo = fs::read_to_string(&kept[0]).unwrap();
+        let missing = restore_backup(&path, &format!("{}/nope", dir));
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(restored, "old");
+        assert_eq!((kept.len(), undo.as_str()), (2, "new")); // what was there before the restore is a backup too
+        assert!(missing.is_err());
+    }
+}
@@ -1,14 +1,14 @@
 use std::cmp::{Ord, Ordering};
 use serde::{Serialize, Deserialize};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 use super::fighter::{Fighter, Class};
 use super::round::{Arena, Modifier};
 use super::batlog::Batlog;
 
-pub fn battle(f1: &mut Fighter, f2: &mut Fighter, arena: &Arena, modifier: &Modifier, log: &mut Batlog) {
-    let f1_stats = f1.roll_for_stats(arena, modifier);
-    let f2_stats = f2.roll_for_stats(arena, modifier);
+pub fn battle(f1: &mut Fighter, f2: &mut Fighter, arena: &Arena, modifier: &Modifier, log: &mut Batlog, rng: &mut impl Rng) {
+    let f1_stats = f1.roll_for_stats(arena, modifier, rng);
+    let f2_stats = f2.roll_for_stats(arena, modifier, rng);
 
     f1.battles_fought += 1;
     f2.battles_fought += 1;
@@ -29,10 +29,10 @@ pub fn battle(f1: &mut Fighter, f2: &mut Fighter, arena: &Arena, modifier: &Modi
     if let Modifier::OhShitSheHasAGun = modifier {
         let mut f1_shot = false;
         let mut f2_shot = false;
-        if thread_rng().gen_range(0..10) == 0 {
+        if rng.gen_range(0..10) == 0 {
             f1_shot = true
         }
-        if thread_rng().gen_range(0..10) == 0 {
+        if rng.gen_range(0..10) == 0 {
             f2_shot = true
         }
         let events = if f1_shot {
@@ -59,8 +59,8 @@ pub fn battle(f1: &mut Fighter, f2: &mut Fighter, arena: &Arena, modifier: &Modi
 
     match result {
         BattleResult::F1Win | BattleResult::F1WinFromCleric => {
-            f1.injure(arena, modifier, log, false);
-            let inj = f2.injure(arena, modifier, log, true);
+            f1.injure(arena, modifier, log, false, rng);
+            let inj = f2.injure(arena, modifier, log, true, rng);
             let rdiff = f2.rating - f1.rating; // how much bigger is f2s rating
 
             if rdiff > 3 { // double stat ups and rating
@@ -82,8 +82,8 @@ pub fn battle(f1: &mut Fighter, f2: &mut Fighter, arena: &Arena, modifier: &Modi
             }
         }
         BattleResult::F2Win | BattleResult::F2WinFromCleric => {
-            let inj = f1.injure(arena, modifier, log, true);
-            f2.injure(arena, modifier, log, false);
+            let inj = f1.injure(arena, modifier, log, true, rng);
+            f2.injure(arena, modifier, log, false, rng);
             let rdiff = f1.rating - f2.rating; // how much bigger is f1s rating
             
             if rdiff > 3 {
@@ -107,12 +107,12 @@ pub fn battle(f1: &mut Fighter, f2: &mut Fighter, arena: &Arena, modifier: &Modi
         BattleResult::Draw | BattleResult::DrawFromCleric => {
             match arena {
                 Arena::CrocPit => {
-                    f1.injure(arena, modifier, log, true);
-                    f2.injure(arena, modifier, log, true);
+                    f1.injure(arena, modifier, log, true, rng);
+                    f2.injure(arena, modifier, log, true, rng);
                 }
                 _ => {
-                    f1.injure(arena, modifier, log, false);
-                    f2.injure(arena, modifier, log, false);
+                    f1.injure(arena, modifier, log, false, rng);
+                    f2.injure(arena, modifier, log, false, rng);
                 }
             }
         }
@@ -208,12 +208,26 @@ pub enum BattleResult {
     DrawFromCleric
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for BattleResult {
     fn default() -> Self {
         Self::Draw
     }
 }
 
+impl BattleResult {
+    pub fn name(&self) -> &'static str { // what json and csv output call it. spelled out rather than taken from the enum, so renaming a variant can't break anyone's bot or sheet
+        match self {
+            BattleResult::F1Win => "fighter_1",
+            BattleResult::F2Win => "fighter_2",
+            BattleResult::F1WinFromCleric => "fighter_1_cleric",
+            BattleResult::F2WinFromCleric => "fighter_2_cleric",
+            BattleResult::Draw => "draw",
+            BattleResult::DrawFromCleric => "draw_cleric"
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
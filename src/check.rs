@@ -0,0 +1,243 @@
+use std::fmt;
+
+use crate::game::GameState;
+use crate::round::Round;
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub explanation: String,
+    pub fix: Fix,
+}
+
+#[derive(Debug, Clone)]
+pub enum Fix {
+    Auto(String, bool), // what --repair does about it, and whether it's been done
+    Manual(String), // what the gm should do instead
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.explanation)?;
+        match &self.fix {
+            Fix::Auto(d, true) => write!(f, "\tfixed: {}", d),
+            Fix::Auto(d, false) => write!(f, "\t--repair will {}", d),
+            Fix::Manual(d) => write!(f, "\tneeds fixing by hand: {}", d)
+        }
+    }
+}
+
+struct Checker {
+    problems: Vec<Problem>,
+    repair: bool,
+}
+
+impl Checker {
+    fn auto(&mut self, explanation: String, fix: &str) -> bool { // returns whether to go ahead with the repair
+        self.problems.push(Problem { explanation, fix: Fix::Auto(fix.to_string(), self.repair) });
+        self.repair
+    }
+    fn manual(&mut self, explanation: String, hint: &str) {
+        self.problems.push(Problem { explanation, fix: Fix::Manual(hint.to_string()) })
+    }
+}
+
+pub fn check_game(game: &mut GameState, repair: bool) -> Vec<Problem> { // finds everything wrong with a save. only fixes things if repair is set
+    let mut c = Checker { problems: Vec::new(), repair };
+    let n = game.fighters.len();
+
+    for (i, f) in game.fighters.iter().enumerate() {
+        if let Err(e) = f.validate() {
+            c.manual(format!("fighter {} ({}) is invalid: {}", i, f.name, e), "use edit-fighter to correct it")
+        }
+    }
+
+    if game.num_rounds as usize != game.prev_rounds.len() || game.num_rounds < 0 {
+        let msg = format!("num_rounds is {} but {} rounds have been played", game.num_rounds, game.prev_rounds.len());
+        if c.auto(msg, "set num_rounds to the number of rounds played") {
+            game.num_rounds = game.prev_rounds.len() as i32
+        }
+    }
+
+    for (ri, round) in game.prev_rounds.iter_mut().enumerate() {
+        let r = match round {
+            Round::Standard(r) => r,
+            Round::Boss(_) => continue
+        };
+        let name = format!("round {}", ri);
+        if r.log.round_no != ri as i32 + 1 {
+            let msg = format!("{} is numbered {} in its log, should be {}", name, r.log.round_no, ri + 1);
+            if c.auto(msg, "renumber it") {
+                r.log.round_no = ri as i32 + 1
+            }
+        }
+        if !r.run {
+            c.manual(format!("{} is in the list of past rounds but was never run", name), "remove it from prev_rounds in the save file")
+        }
+        for (a, b) in &r.matchups {
+            if *a >= n || *b >= n {
+                c.manual(format!("{} has a matchup between fighters {} and {}, but there are only {} fighters", name, a, b, n), "fix the matchup in the save file")
+            }
+        }
+        if let Some(s) = r.sitting_out {
+            if s >= n {
+                let msg = format!("{} has fighter {} sitting out, but there are only {} fighters", name, s, n);
+                if c.auto(msg, "forget who sat out") {
+                    r.sitting_out = None
+                }
+            }
+        }
+        if r.run && r.log.fights.len() != r.matchups.len() {
+            c.manual(format!("{} has {} matchups but {} battles in its log", name, r.matchups.len(), r.log.fights.len()), "make the matchups and battles agree in the save file")
+        }
+        for (b, (m1, m2)) in r.log.fights.iter().zip(&r.matchups) {
+            if b.fighter_1 != *m1 || b.fighter_2 != *m2 {
+                c.manual(format!("{} has a battle between {} and {}, but the matchup is {} vs {}", name, b.fighter_1, b.fighter_2, m1, m2), "make the matchups and battles agree in the save file")
+            }
+        }
+    }
+
+    let expected_round_no = game.num_rounds + 1;
+    let mut scheduled: Vec<usize> = Vec::new(); // fighters in the scheduled round
+    if let Some(Round::Standard(r)) = &mut game.next_round {
+        if r.run {
+            c.manual(String::from("the scheduled round is marked as already run"), "cancel-round and then new-round")
+        }
+        if r.log.round_no != expected_round_no {
+            let msg = format!("the scheduled round is numbered {}, should be {}", r.log.round_no, expected_round_no);
+            if c.auto(msg, "renumber it") {
+                r.log.round_no = expected_round_no
+            }
+        }
+        let bad: Vec<(usize, usize)> = r.matchups.iter().filter(|(a, b)| *a >= n || *b >= n).cloned().collect();
+        for (a, b) in &bad {
+            let msg = format!("the scheduled round has a matchup between fighters {} and {}, but there are only {} fighters", a, b, n);
+            c.auto(msg, "remove that matchup");
+        }
+        if repair {
+            r.matchups.retain(|(a, b)| *a < n && *b < n);
+        }
+        for (a, b) in r.matchups.iter().filter(|m| !bad.contains(m)) { // without --repair the bad ones are still there, and would panic here
+            for f in [a, b] {
+                if game.fighters[*f].dead {
+                    c.manual(format!("fighter {} ({}) is dead but is in the scheduled round", f, game.fighters[*f].name), "cancel-round and then new-round")
+                }
+                if scheduled.contains(f) {
+                    c.manual(format!("fighter {} ({}) is in the scheduled round more than once", f, game.fighters[*f].name), "cancel-round and then new-round")
+                }
+                scheduled.push(*f)
+            }
+        }
+        if let Some(s) = r.sitting_out {
+            if s >= n {
+                let msg = format!("the scheduled round has fighter {} sitting out, but there are only {} fighters", s, n);
+                if c.auto(msg, "forget who is sitting out") {
+                    r.sitting_out = None
+                }
+            }
+        }
+    }
+
+    let mut keep = Vec::new();
+    let mut pre_matched: Vec<usize> = Vec::new();
+    for (a, b) in game.pre_matches.clone() {
+        let reason = if a >= n || b >= n {
+            Some(format!("there are only {} fighters", n))
+        }
+        else if a == b {
+            Some(String::from("a fighter can't fight themself"))
+        }
+        else if game.fighters[a].dead || game.fighters[b].dead {
+            Some(String::from("one of them is dead"))
+        }
+        else {
+            None
+        };
+        match reason {
+            Some(r) => {
+                let msg = format!("arranged match between fighters {} and {} is impossible: {}", a, b, r);
+                if !c.auto(msg, "cancel the arranged match") {
+                    keep.push((a, b))
+                }
+            }
+            None => {
+                for f in [a, b] {
+                    if pre_matched.contains(&f) || scheduled.contains(&f) {
+                        c.manual(format!("fighter {} ({}) is booked into more than one match", f, game.fighters[f].name), "fix pre_matches in the save file")
+                    }
+                    pre_matched.push(f)
+                }
+                keep.push((a, b))
+            }
+        }
+    }
+    game.pre_matches = keep;
+
+    for (i, f) in game.fighters.iter_mut().enumerate() {
+        let booked = pre_matched.contains(&i) || scheduled.contains(&i);
+        if f.pre_matched && !booked {
+            let msg = format!("fighter {} ({}) is flagged as having an arranged match, but doesn't have one", i, f.name);
+            if c.auto(msg, "clear the flag") {
+                f.pre_matched = false
+            }
+        }
+        else if !f.pre_matched && pre_matched.contains(&i) {
+            let msg = format!("fighter {} ({}) has an arranged match but isn't flagged, so they could get matched twice", i, f.name);
+            if c.auto(msg, "set the flag") {
+                f.pre_matched = true
+            }
+        }
+    }
+
+    c.problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighter::{Fighter, Class};
+    use crate::round::Pairing;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_check_and_repair() {
+        let mut game = GameState::new_game("test");
+        for name in ["a", "b", "c"] {
+            game.add_fighter(Fighter::new(String::from(name), String::from("o"), Class::Mutant, 4, 4, 4));
+        }
+        assert!(game.check().is_empty());
+
+        game.num_rounds = 2;
+        game.pre_matches.push((0, 5));
+        game.pre_matches.push((1, 2)); // fine apart from the flags
+        game.fighters[0].pre_matched = true;
+        assert_eq!(game.check().len(), 5);
+
+        let problems = check_game(&mut game, true);
+        assert!(problems.iter().all(|p| matches!(p.fix, Fix::Auto(_, true))));
+        assert!(game.check().is_empty());
+        assert_eq!(game.num_rounds, 0);
+        assert_eq!(game.pre_matches, vec![(1, 2)]);
+        assert!(!game.fighters[0].pre_matched && game.fighters[1].pre_matched && game.fighters[2].pre_matched);
+    }
+
+    #[test]
+    fn test_check_bad_scheduled_matchup() {
+        let mut game = GameState::new_game("test");
+        for name in ["a", "b", "c", "d"] {
+            game.add_fighter(Fighter::new(String::from(name), String::from("o"), Class::Mutant, 4, 4, 4));
+        }
+        game.new_round(None, None, Pairing::Random, &mut ChaCha8Rng::seed_from_u64(1)).unwrap();
+        let n = game.fighters.len();
+        if let Some(Round::Standard(r)) = &mut game.next_round {
+            r.matchups.push((0, n))
+        }
+
+        let problems = check_game(&mut game, false); // used to index fighters[n] and panic
+        let bad = problems.iter().find(|p| p.explanation.contains(&format!("fighters 0 and {}", n))).unwrap();
+        assert!(matches!(bad.fix, Fix::Auto(_, false)));
+
+        check_game(&mut game, true);
+        assert!(matches!(&game.next_round, Some(Round::Standard(r)) if r.matchups.iter().all(|(a, b)| *a < n && *b < n)));
+    }
+}
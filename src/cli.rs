@@ -0,0 +1,310 @@
+use std::io::{stdin, BufRead, Write, stderr};
+use std::path::Path;
+use std::cmp::Reverse;
+use std::fmt;
+use serde_json::{Value, json};
+
+use gladiator_war::global::GwGlobalData;
+use gladiator_war::game::GameState;
+use gladiator_war::journal::Action;
+use gladiator_war::check::{Problem, Fix};
+use gladiator_war::hall::{HallOfFame, Career};
+use gladiator_war::report::{Report, Outcome};
+use gladiator_war::error::GameError;
+use gladiator_war::output::Format;
+use gladiator_war::render::Layout;
+use gladiator_war::fighter::Fighter;
+
+// the text half of the command line. the library hands back data, and this is where it gets printed
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GwError { // every way a command can fail. each kind has its own exit code, so scripts can tell them apart (see the readme)
+    Rule(String), // the command made sense, but the game doesn't allow it
+    Parse(String), // bad arguments. empty if argparse already said what was wrong
+    MissingSave(String), // no save by that name, or its file is gone
+    Io(String), // a file couldn't be read or written
+    InvalidIndex(String), // a fighter, round or backup number that doesn't exist
+    Busy(String), // another command has the save locked, or changed it while we were working
+}
+
+impl GwError {
+    pub fn code(&self) -> i32 {
+        match self {
+            GwError::Rule(_) => 1,
+            GwError::Parse(_) => 2, // same as argparse uses
+            GwError::MissingSave(_) => 3,
+            GwError::Io(_) => 4,
+            GwError::InvalidIndex(_) => 5,
+            GwError::Busy(_) => 6,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            GwError::Rule(m) | GwError::Parse(m) | GwError::MissingSave(m) | GwError::Io(m) | GwError::InvalidIndex(m) | GwError::Busy(m) => m
+        }
+    }
+
+    pub fn with_message(self, message: String) -> GwError { // same kind (and exit code), different words
+        match self {
+            GwError::Rule(_) => GwError::Rule(message),
+            GwError::Parse(_) => GwError::Parse(message),
+            GwError::MissingSave(_) => GwError::MissingSave(message),
+            GwError::Io(_) => GwError::Io(message),
+            GwError::InvalidIndex(_) => GwError::InvalidIndex(message),
+            GwError::Busy(_) => GwError::Busy(message),
+        }
+    }
+}
+
+impl fmt::Display for GwError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl From<GameError> for GwError {
+    fn from(e: GameError) -> Self {
+        let message = e.to_string();
+        match e {
+            GameError::FighterOutOfRange(_) | GameError::RoundOutOfRange(_) | GameError::SaveOutOfRange(_) => GwError::InvalidIndex(message),
+            GameError::Io(_) => GwError::Io(message),
+            GameError::Conflict(_) => GwError::Busy(message),
+            GameError::Parse(_) => GwError::Parse(message),
+            GameError::NotFound(_) => GwError::MissingSave(message),
+            GameError::NoRoundScheduled | GameError::Rule(_) => GwError::Rule(message)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ProgramOptions {
+    pub global_data: GwGlobalData,
+    pub verbosity: i32,
+    pub logging: bool,
+    pub format: Format,
+}
+
+impl ProgramOptions {
+    #[allow(dead_code)]
+    pub fn new(global_data: GwGlobalData) -> Self {
+        ProgramOptions {
+            global_data,
+            ..ProgramOptions::default()
+        }
+    }
+}
+
+pub fn confirm(prompt: &str) -> bool {
+    confirm_from(prompt, &mut stdin().lock())
+}
+
+fn confirm_from(prompt: &str, input: &mut impl BufRead) -> bool { // everything goes to stderr, so it doesn't end up in the middle of --format json output
+    loop {
+        eprint!("{} ", prompt);
+        let _ = stderr().flush();
+        let mut buf = String::new();
+        match input.read_line(&mut buf) {
+            Ok(0) | Err(_) => return false, // nobody there to answer (eg a bot with stdin closed), so it's a no
+            Ok(_) => {}
+        }
+        match buf.trim().to_lowercase().as_str() {
+            "yes"|"y" => {
+                return true
+            }
+            "no"|"n" => {
+                return false
+            }
+            _ => {
+                eprintln!("please select y/n")
+            }
+        }
+    }
+}
+
+pub fn print_json(v: &Value) {
+    println!("{}", serde_json::to_string_pretty(v).unwrap())
+}
+
+pub fn error(format: Format, e: impl Into<GwError>) -> i32 { // reports an error the way the format wants, and hands back the exit code
+    let e = e.into();
+    match format {
+        Format::Text if e.message().is_empty() => {} // argparse already said what was wrong
+        Format::Text => println!("{}", e),
+        Format::Json => eprintln!("{}", json!({ "error": e.message(), "code": e.code() }))
+    }
+    e.code()
+}
+
+pub fn apply(game: &mut GameState, mut action: Action, po: &ProgramOptions) -> Result<Outcome, GameError> { // runs an action, journals it, and logs the round if it was one that ran
+    let outcome = game.apply(&mut action)?;
+    if outcome.changed {
+        game.record(action)
+    }
+    if let (Report::RoundRun(_), true) = (&outcome.report, po.logging) {
+        let config = &po.global_data.config;
+        let logged = Layout::pick(None, None, &config.round_template, config.log_style)
+            .and_then(|l| game.log_round(game.prev_rounds.len() - 1, None, &l, config));
+        match logged {
+            Ok(_) => {}
+            Err(e) if po.verbosity > -2 => println!("{}", e), // the round still happened, so this isn't worth failing over
+            Err(_) => {}
+        }
+    }
+    Ok(outcome)
+}
+
+pub fn print_report(report: &Report, po: &ProgramOptions) {
+    if po.verbosity < 0 {
+        return
+    }
+    match report {
+        Report::StatsAdded { fighter, stats } => println!("adding stats {}, {}, {} to fighter {}", stats[0], stats[1], stats[2], fighter),
+        Report::FightersAdded(fighters) => {
+            for f in fighters {
+                println!("added {} as fighter {}", f.name, f.index)
+            }
+        }
+        Report::Edited(edits) => {
+            if edits.is_empty() {
+                println!("nothing changed")
+            }
+            for e in edits {
+                println!("{}", e)
+            }
+        }
+        Report::RoundScheduled(r) | Report::RoundRun(r) => println!("{}", r),
+        Report::RoundCancelled(n) => println!("cancelling round {}", n),
+        Report::Undone(commands) => {
+            for c in commands {
+                println!("undid {}", c)
+            }
+        }
+        Report::Repaired(problems) => print_problems(problems),
+        Report::Nothing | Report::MatchArranged { .. } => {}
+    }
+}
+
+pub fn print_problems(problems: &[Problem]) {
+    for p in problems {
+        println!("{}", p)
+    }
+    let fixed = problems.iter().filter(|p| matches!(p.fix, Fix::Auto(_, true))).count();
+    let fixable = problems.iter().filter(|p| matches!(p.fix, Fix::Auto(_, false))).count();
+    match problems.len() {
+        0 => println!("no problems found"),
+        n => println!("{} problems found, {} fixed, {} fixable with --repair", n, fixed, fixable)
+    }
+}
+
+pub fn list_saves(global: &GwGlobalData) {
+    println!("printing save games:");
+    for (i, e) in global.saves.iter().enumerate() {
+        let alias = match &e.alias {
+            Some(a) => format!(" ({})", a),
+            None => String::new()
+        };
+        let missing = if Path::new(&e.path).exists() { "" } else { "  MISSING" };
+        let archived = if e.archived { " (archived)" } else { "" };
+        println!("[#{}] {}{}: {}{}", i, e.name, alias, e.season_name, archived);
+        println!("\tcreated {}, last modified {}", e.created, e.last_modified);
+        println!("\t{}{}", e.path, missing);
+    }
+}
+
+type Stat = fn(&Career) -> i32;
+
+pub fn print_hall_of_fame(hall: &HallOfFame, top: usize) {
+    let categories: [(&str, Stat); 6] = [
+        ("most kills", |c| c.kills),
+        ("most battles won", |c| c.battles_won),
+        ("highest peak rating", |c| c.peak_rating),
+        ("longest survival (rounds)", |c| c.rounds_survived),
+        ("most championships", |c| c.championships),
+        ("most battles fought", |c| c.battles_fought),
+    ];
+    for (title, key) in categories {
+        println!("{}:", title);
+        let mut ranked: Vec<&Career> = hall.careers.iter().filter(|c| key(c) > 0).collect();
+        ranked.sort_by_key(|c| Reverse(key(c)));
+        if ranked.is_empty() {
+            println!("\tnobody yet")
+        }
+        for (i, c) in ranked.iter().take(top).enumerate() {
+            println!("\t{}. {} ({}) - {}{}  [{}]", i + 1, c.name, c.owner, key(c), if c.dead { " (dead)" } else { "" }, c.seasons.join(", "))
+        }
+    }
+    println!("owners with the most championships:");
+    let owners = hall.owner_championships();
+    if owners.is_empty() {
+        println!("\tnobody yet")
+    }
+    for (i, (owner, n)) in owners.iter().take(top).enumerate() {
+        println!("\t{}. {} - {}", i + 1, owner, n)
+    }
+    for s in &hall.skipped {
+        println!("skipped {}", s)
+    }
+}
+
+pub fn print_lineage(seasons: &[(String, usize, Fighter)]) {
+    for (season, _, f) in seasons {
+        println!("{}: {} (rating {}, {} kills, won {}/{}){}", season, f.name, f.rating, f.kills, f.battles_won, f.battles_fought, if f.dead { " (dead)" } else { "" });
+    }
+}
+
+pub fn list_history(game: &GameState) {
+    if game.history().is_empty() {
+        println!("no history recorded")
+    }
+    let len = game.history().len();
+    for (i, e) in game.history().iter().enumerate() {
+        println!("[{}] {}", len - i, e) // numbered by how many undos it takes to get back there
+    }
+}
+
+pub fn list_edits(game: &GameState) {
+    if game.edits.is_empty() {
+        println!("no edits recorded")
+    }
+    for e in &game.edits {
+        println!("{}", e)
+    }
+}
+
+pub fn show_journal(game: &GameState) {
+    if !game.journal_mode {
+        println!("journal mode is off")
+    }
+    for e in game.journal() {
+        println!("{}", e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let all = [GwError::Rule(String::new()), GwError::Parse(String::new()), GwError::MissingSave(String::new()),
+            GwError::Io(String::new()), GwError::InvalidIndex(String::new()), GwError::Busy(String::new())];
+        let codes: Vec<i32> = all.iter().map(|e| e.code()).collect();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6]); // documented in the readme, so don't shuffle these
+        assert_eq!(GwError::from(GameError::FighterOutOfRange(3)), GwError::InvalidIndex(String::from("fighter index 3 out of range")));
+        assert_eq!(GwError::from(GameError::SaveOutOfRange(3)).code(), 5);
+        assert_eq!(GwError::from(GameError::NoRoundScheduled).code(), 1);
+        assert_eq!(GwError::from(GameError::Conflict(String::from("x"))).code(), 6);
+        assert_eq!(GwError::from(GameError::Parse(String::from("x"))).code(), 2);
+        assert_eq!(GwError::from(GameError::NotFound(String::from("x"))).code(), 3);
+        assert_eq!(GwError::Io(String::from("x")).with_message(String::from("y")), GwError::Io(String::from("y")));
+    }
+
+    #[test]
+    fn test_confirm() {
+        assert!(confirm_from("sure?", &mut "maybe\nY\n".as_bytes()));
+        assert!(!confirm_from("sure?", &mut "n\n".as_bytes()));
+        assert!(!confirm_from("sure?", &mut "".as_bytes())); // stdin closed, rather than asking forever
+        assert!(!confirm_from("sure?", &mut "what\n".as_bytes()));
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::season::CarryOver;
+use crate::output::Format;
+use crate::render::Style;
+use crate::round::Pairing;
+use crate::template::Template;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)] // keys added after a file was written just take their default
+pub struct Config { // defaults for things that would otherwise need passing every time
+    pub verbosity: i32, // -v and -q go up and down from here
+    pub format: Format, // same as passing --format
+    pub auto_log: bool, // same as always passing -l
+    pub log_dir: String, // where automatic logs go, empty for the current folder
+    pub log_name: String, // %S is the season name, %R the round number
+    pub log_style: Style, // the log format, how log-round and -l write rounds
+    pub round_template: String, // paths to the user's templates, empty for the built in look
+    pub fighter_template: String,
+    pub standings_template: String,
+    pub pairing: Pairing, // same as passing -p to new-round
+    pub keep_stats: i32, // new-season carry over
+    pub keep_rating: bool,
+    pub kill_bonus: i32,
+    // no default rules preset yet, there are no presets to choose between
+}
+impl Default for Config {
+    fn default() -> Self {
+        let carry = CarryOver::default();
+        Config {
+            verbosity: 0,
+            format: Format::Text,
+            auto_log: false,
+            log_dir: String::new(),
+            log_name: String::from("%S_batlog_%R.txt"),
+            log_style: Style::Text,
+            round_template: String::new(),
+            fighter_template: String::new(),
+            standings_template: String::new(),
+            pairing: Pairing::Random,
+            keep_stats: carry.keep_stats,
+            keep_rating: carry.keep_rating,
+            kill_bonus: carry.kill_bonus
+        }
+    }
+}
+
+impl Config {
+    pub fn carry_over(&self) -> CarryOver {
+        CarryOver {
+            keep_stats: self.keep_stats,
+            keep_rating: self.keep_rating,
+            kill_bonus: self.kill_bonus
+        }
+    }
+
+    pub fn validate(&self, key: &str) -> Result<(), String> { // only checks the one setting, so a template that broke since it was set doesn't block changing something else
+        match key {
+            "keep_stats" | "keep_rating" | "kill_bonus" => self.carry_over().validate(),
+            "log_name" if self.log_name.trim().is_empty() => Err(String::from("log_name cannot be empty")),
+            "round_template" | "fighter_template" | "standings_template" => {
+                let t = self.get(key)?;
+                if !t.is_empty() {
+                    Template::load(&t)?; // catch typos now rather than on the next run-round
+                }
+                Ok(())
+            }
+            _ => Ok(())
+        }
+    }
+
+    fn fields(&self) -> serde_json::Map<String, Value> {
+        match serde_json::to_value(self).unwrap() {
+            Value::Object(m) => m,
+            _ => unreachable!()
+        }
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.fields().keys().cloned().collect()
+    }
+
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        match self.fields().get(key) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(v) => Ok(v.to_string()),
+            None => Err(format!("no config key {} (try one of {})", key, self.keys().join(", ")))
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> { // only changes anything if the new value makes sense
+        let mut fields = self.fields();
+        let new = match fields.get(key) {
+            Some(Value::String(_)) => Value::String(value.to_string()),
+            Some(_) => match serde_json::from_str::<Value>(value) {
+                Ok(v) => v,
+                Err(_) => return Err(format!("{} is not a valid value for {}", value, key))
+            }
+            None => return Err(format!("no config key {} (try one of {})", key, self.keys().join(", ")))
+        };
+        fields.insert(key.to_string(), new);
+        let config: Config = match serde_json::from_value(Value::Object(fields)) {
+            Ok(c) => c,
+            Err(_) => return Err(format!("{} is not a valid value for {} (expected something like {})", value, key, self.get(key)?))
+        };
+        config.validate(key)?;
+        *self = config;
+        Ok(())
+    }
+
+    pub fn unset(&mut self, key: &str) -> Result<(), String> { // back to the default
+        let default = Config::default().get(key)?;
+        self.set(key, &default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_set() {
+        let mut c = Config::default();
+        c.set("verbosity", "2").unwrap();
+        c.set("auto_log", "true").unwrap();
+        c.set("log_name", "123").unwrap(); // strings stay strings
+        assert_eq!((c.verbosity, c.auto_log, c.log_name.as_str()), (2, true, "123"));
+        assert!(c.set("verbosity", "loud").is_err());
+        assert!(c.set("keep_stats", "150").is_err());
+        c.set("log_dir", "not/made/yet").unwrap(); // only checked when a log is written
+        assert!(c.set("nope", "1").is_err());
+        assert_eq!(c.verbosity, 2);
+        c.unset("verbosity").unwrap();
+        assert_eq!(c.get("verbosity"), Ok(String::from("0")));
+        c.set("format", "json").unwrap();
+        assert!(c.set("format", "xml").is_err());
+        c.set("log_style", "discord").unwrap();
+        assert_eq!(c.log_style, Style::Discord);
+        c.set("pairing", "rating").unwrap();
+        assert_eq!(c.pairing, Pairing::Rating);
+        assert!(c.set("pairing", "alphabetical").is_err());
+        assert!(c.set("round_template", "/definitely/not/a/template").is_err());
+        c.round_template = String::from("/a/template/that/was/deleted");
+        c.set("verbosity", "1").unwrap(); // other settings don't care
+    }
+
+    #[test]
+    fn test_missing_keys() {
+        let c: Config = serde_json::from_str(r#"{"verbosity": 1, "log_name": "x.txt"}"#).unwrap();
+        assert_eq!((c.verbosity, c.log_name.as_str()), (1, "x.txt"));
+        assert_eq!(c.format, Format::Text);
+        assert_eq!(c.round_template, "");
+    }
+}
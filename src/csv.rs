@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+
+use crate::game::GameState;
+use crate::round::Round;
+use crate::utils::{fmt_option, write_atomic};
+use crate::error::GameError;
+
+// spreadsheet friendly dumps of a season. the columns are documented in the readme and people build sheets on top of them,
+// so only ever add new columns on the end
+
+pub const FIGHTER_COLUMNS: [&str; 19] = ["season", "index", "name", "owner", "class", "strength", "speed", "skill", "total", "unspent_points",
+    "rating", "kills", "battles_won", "battles_fought", "dead", "pre_matched", "previous_season", "previous_path", "previous_index"];
+pub const ROUND_COLUMNS: [&str; 8] = ["season", "round_no", "kind", "arena", "modifier", "battles", "sitting_out", "sitting_out_name"];
+pub const BATTLE_COLUMNS: [&str; 20] = ["season", "round_no", "battle_no", "fighter_1", "fighter_1_name", "fighter_2", "fighter_2_name",
+    "strength_1", "speed_1", "skill_1", "strength_2", "speed_2", "skill_2", "injury_1", "injury_2", "points", "result", "winner", "winner_name", "events"];
+
+fn cell(s: &str) -> String { // quoted only if it has to be
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn table(columns: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut ret = columns.join(",");
+    ret.push_str("\r\n"); // what rfc 4180 asks for, and every spreadsheet is happy with it
+    for r in rows {
+        ret.push_str(&r.iter().map(|c| cell(c)).collect::<Vec<String>>().join(","));
+        ret.push_str("\r\n")
+    }
+    ret
+}
+
+fn roll(rolls: &[i32], i: usize) -> String { // empty if the battle never got as far as rolling
+    rolls.get(i).map(|r| r.to_string()).unwrap_or_default()
+}
+
+pub fn fighters(game: &GameState) -> String {
+    let rows = game.fighters.iter().enumerate().map(|(i, f)| vec![
+        game.season_name.clone(), i.to_string(), f.name.clone(), f.owner.clone(), f.class.to_string(),
+        f.strength.to_string(), f.speed.to_string(), f.skill.to_string(), f.total().to_string(), f.unspent_points.to_string(),
+        f.rating.to_string(), f.kills.to_string(), f.battles_won.to_string(), f.battles_fought.to_string(), f.dead.to_string(), f.pre_matched.to_string(),
+        f.previous.as_ref().map(|p| p.season_name.clone()).unwrap_or_default(),
+        f.previous.as_ref().map(|p| p.path.clone()).unwrap_or_default(),
+        f.previous.as_ref().map(|p| p.fighter.to_string()).unwrap_or_default()
+    ]).collect();
+    table(&FIGHTER_COLUMNS, rows)
+}
+
+pub fn rounds(game: &GameState) -> String { // only rounds that have been run
+    let rows = game.prev_rounds.iter().filter_map(|r| match r {
+        Round::Standard(_) => game.report_round(r).ok(),
+        Round::Boss(_) => None // not implemented
+    }).map(|r| {
+        let sitting_out = r.sitting_out.as_ref();
+        vec![
+            game.season_name.clone(), r.round_no.to_string(), String::from("standard"), r.arena.to_string(), r.modifier.to_string(), r.matchups.len().to_string(),
+            sitting_out.map(|s| s.index.to_string()).unwrap_or_default(), sitting_out.map(|s| s.name.clone()).unwrap_or_default()
+        ]
+    }).collect();
+    table(&ROUND_COLUMNS, rows)
+}
+
+pub fn battles(game: &GameState) -> String {
+    let mut rows = Vec::new();
+    for r in &game.prev_rounds {
+        let report = match game.report_round(r) {
+            Ok(r) => r,
+            Err(_) => continue // boss round, not implemented
+        };
+        for (i, m) in report.matchups.iter().enumerate() {
+            let b = match &m.battle {
+                Some(b) => b,
+                None => continue
+            };
+            let winner = m.winner();
+            rows.push(vec![
+                game.season_name.clone(), report.round_no.to_string(), (i + 1).to_string(),
+                m.fighter_1.index.to_string(), m.fighter_1.name.clone(), m.fighter_2.index.to_string(), m.fighter_2.name.clone(),
+                roll(&b.rolls_1, 0), roll(&b.rolls_1, 1), roll(&b.rolls_1, 2), roll(&b.rolls_2, 0), roll(&b.rolls_2, 1), roll(&b.rolls_2, 2),
+                fmt_option(&b.injury_1), fmt_option(&b.injury_2), b.points.to_string(), b.result.name().to_string(),
+                winner.map(|w| w.index.to_string()).unwrap_or_default(), winner.map(|w| w.name.clone()).unwrap_or_default(),
+                b.other_events.join("; ")
+            ])
+        }
+    }
+    table(&BATTLE_COLUMNS, rows)
+}
+
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>, String> { // reads what spreadsheets write: quoted cells can have commas, quotes and line breaks in them
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text); // excel likes to start with a byte order mark
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if cell.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut cell)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row))
+            }
+            (c, _) => cell.push(c)
+        }
+    }
+    if quoted {
+        return Err(String::from("unclosed quote"))
+    }
+    if !cell.is_empty() || !row.is_empty() { // no line break at the end
+        row.push(cell);
+        rows.push(row)
+    }
+    Ok(rows)
+}
+
+pub fn export_csv(game: &GameState, dir: &str) -> Result<Vec<String>, GameError> { // writes fighters.csv, rounds.csv and battles.csv into dir, replacing any that are there
+    if let Err(e) = fs::create_dir_all(dir) {
+        return Err(GameError::Io(format!("could not create {}: {}", dir, e)))
+    }
+    let mut written = Vec::new();
+    for (name, text) in [("fighters.csv", fighters(game)), ("rounds.csv", rounds(game)), ("battles.csv", battles(game))] {
+        let path = Path::new(dir).join(name).to_string_lossy().to_string();
+        if let Err(e) = write_atomic(&path, text) {
+            return Err(GameError::Io(format!("could not write {}: {}", path, e)))
+        }
+        written.push(path)
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battle::BattleResult;
+    use crate::testing::{season, play, temp_path};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn sheet() -> GameState { // one round run: one battle, and one of the three sits out
+        let mut game = season("sheet", &["bob", "al", "cy"]);
+        play(&mut game, 1, &mut ChaCha8Rng::seed_from_u64(1));
+        game
+    }
+
+    #[test]
+    fn test_rounds() {
+        let r = rounds(&sheet());
+        let row = r.split("\r\n").nth(1).unwrap();
+        assert_eq!(row.split(',').count(), ROUND_COLUMNS.len());
+        assert!(row.starts_with("sheet,1,standard,"));
+    }
+
+    #[test]
+    fn test_battles() {
+        let b = battles(&sheet());
+        let row: Vec<&str> = b.split("\r\n").nth(1).unwrap().split(',').collect();
+        assert_eq!(b.split("\r\n").count(), 3); // one battle (the third fighter sat out), and the empty bit after the last line break
+        assert_eq!((row.len(), row[1], row[2]), (BATTLE_COLUMNS.len(), "1", "1"));
+        assert!(row[7].parse::<i32>().is_ok() && row[12].parse::<i32>().is_ok());
+        assert!(["fighter_1", "fighter_2", "draw"].contains(&row[16])); // no clerics here
+        assert_eq!(BattleResult::DrawFromCleric.name(), "draw_cleric");
+    }
+
+    #[test]
+    fn test_fighters() {
+        let mut game = sheet();
+        game.fighters[0].name = String::from("bob \"the, blade\"");
+        let f = fighters(&game);
+        let lines: Vec<&str> = f.split("\r\n").collect();
+        assert_eq!(lines[0], FIGHTER_COLUMNS.join(","));
+        assert!(lines[1].starts_with("sheet,0,\"bob \"\"the, blade\"\"\",o,mutant,"));
+        assert_eq!(lines.len(), 5);
+
+        let back = parse(&f).unwrap(); // and it reads back the same
+        assert_eq!((back.len(), back[1][2].as_str()), (4, "bob \"the, blade\""));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("\u{feff}a,\"b\nc\"\n1,2").unwrap(), vec![vec!["a", "b\nc"], vec!["1", "2"]]);
+        assert!(parse("a,\"b").is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let game = season::<&str>("sheet", &[]);
+        let dir = temp_path("csv");
+        let written = export_csv(&game, &dir).unwrap();
+        assert_eq!(written.len(), 3);
+        export_csv(&game, &dir).unwrap(); // replaces them
+        let mut files: Vec<String> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        files.sort();
+        assert_eq!(files, vec!["battles.csv", "fighters.csv", "rounds.csv"]); // no temp files left behind
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,24 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FighterEdit { // one changed field, kept in the save so everyone can see what the gm fiddled with
+    pub time: String,
+    pub round: i32, // number of rounds played when the edit was made
+    pub fighter: usize, // index into fighter list
+    pub fighter_name: String, // name at the time of the edit, in case the name itself gets changed
+    pub field: String,
+    pub old: String,
+    pub new: String,
+    pub note: Option<String>,
+}
+
+impl fmt::Display for FighterEdit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] after round {}: {} (fighter {}) {}: {} -> {}", self.time, self.round, self.fighter_name, self.fighter, self.field, self.old, self.new)?;
+        if let Some(n) = &self.note {
+            write!(f, " ({})", n)?
+        }
+        Ok(())
+    }
+}
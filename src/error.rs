@@ -0,0 +1,40 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError { // what can go wrong inside the engine, so front ends can tell problems apart without reading the message
+    FighterOutOfRange(usize),
+    RoundOutOfRange(usize),
+    NoRoundScheduled,
+    Io(String), // couldn't read or write a file
+    Conflict(String), // someone else wrote the save while we had it loaded
+    Rule(String), // anything the rules (or the state of the save) don't allow
+    Parse(String), // a value that doesn't parse or doesn't make sense, eg an edit-fighter value
+    NotFound(String), // a save (or a file it points at) that isn't there
+    SaveOutOfRange(usize),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::FighterOutOfRange(i) => write!(f, "fighter index {} out of range", i),
+            GameError::RoundOutOfRange(i) => write!(f, "round {} out of range", i),
+            GameError::SaveOutOfRange(i) => write!(f, "save index {} out of range (use list-saves to see them)", i),
+            GameError::NoRoundScheduled => write!(f, "next round not yet generated! (use new-round first)"),
+            GameError::Io(e) | GameError::Conflict(e) | GameError::Rule(e) | GameError::Parse(e) | GameError::NotFound(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<String> for GameError { // most of the older helpers still report plain strings, and they're all rule problems
+    fn from(e: String) -> Self {
+        GameError::Rule(e)
+    }
+}
+
+impl From<GameError> for String {
+    fn from(e: GameError) -> Self {
+        e.to_string()
+    }
+}
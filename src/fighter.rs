@@ -1,11 +1,12 @@
 use serde::{Serialize, Deserialize};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::fmt;
 use std::str::FromStr;
 
 use super::utils;
 use super::batlog::Batlog;
 use super::round::*;
+use super::season::SeasonLink;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Fighter {
@@ -22,6 +23,7 @@ pub struct Fighter {
     pub battles_fought: i32,
     pub unspent_points: i32,
     pub pre_matched: bool, // whether the player has organised a matchup
+    pub previous: Option<SeasonLink>, // their record last season, if they were carried over
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
@@ -38,6 +40,7 @@ pub enum Class {
     // called "senator" in game for reasons of 14 year olds
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for Class {
     fn default() -> Class {Class::Mutant}
 }
@@ -50,6 +53,7 @@ impl Fighter {
         }
     }
 
+    #[allow(clippy::useless_format, clippy::question_mark)]
     pub fn from_vec(v: &[String]) -> Result<Fighter, String> { // used to add fighters from the command line
         match v.len() { // check you have all the fields
             6 => {}
@@ -80,24 +84,95 @@ impl Fighter {
         Ok(Fighter::new(name, owner, class, strength, speed, skill))
     }
 
+    pub const EDITABLE_FIELDS: [&'static str; 12] = ["name", "owner", "class", "strength", "speed", "skill", "dead", "rating", "kills", "won", "fought", "unspent"];
+
+    pub fn get_field(&self, field: &str) -> Result<String, String> { // string version of a field, for the audit trail
+        Ok(match field {
+            "name" => self.name.clone(),
+            "owner" => self.owner.clone(),
+            "class" => self.class.to_string(),
+            "strength" => self.strength.to_string(),
+            "speed" => self.speed.to_string(),
+            "skill" => self.skill.to_string(),
+            "dead" => self.dead.to_string(),
+            "rating" => self.rating.to_string(),
+            "kills" => self.kills.to_string(),
+            "won" => self.battles_won.to_string(),
+            "fought" => self.battles_fought.to_string(),
+            "unspent" => self.unspent_points.to_string(),
+            _ => return Err(format!("field {} not recognised!", field))
+        })
+    }
+
+    pub fn set_field(&mut self, field: &str, value: &str) -> Result<(), String> { // used by edit-fighter
+        fn parse_int(field: &str, value: &str) -> Result<i32, String> {
+            match value.parse::<i32>() {
+                Ok(v) => Ok(v),
+                Err(_) => Err(format!("{} value {} failed to parse!", field, value))
+            }
+        }
+
+        match field {
+            "name" => self.name = value.to_string(),
+            "owner" => self.owner = value.to_string(),
+            "class" => self.class = value.parse::<Class>()?,
+            "strength" => self.strength = parse_int(field, value)?,
+            "speed" => self.speed = parse_int(field, value)?,
+            "skill" => self.skill = parse_int(field, value)?,
+            "dead" => {
+                self.dead = match value.to_lowercase().as_str() {
+                    "true" | "yes" | "y" => true,
+                    "false" | "no" | "n" => false,
+                    _ => return Err(format!("dead value {} failed to parse! (expected true/false)", value))
+                }
+            }
+            "rating" => self.rating = parse_int(field, value)?,
+            "kills" => self.kills = parse_int(field, value)?,
+            "won" => self.battles_won = parse_int(field, value)?,
+            "fought" => self.battles_fought = parse_int(field, value)?,
+            "unspent" => self.unspent_points = parse_int(field, value)?,
+            _ => return Err(format!("field {} not recognised!", field))
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> { // catch edits that leave the fighter in a silly state
+        if self.name.trim().is_empty() {
+            return Err(String::from("fighter name cannot be empty!"))
+        }
+        if self.owner.trim().is_empty() {
+            return Err(String::from("fighter owner cannot be empty!"))
+        }
+        for (stat, v) in [("kills", self.kills), ("won", self.battles_won), ("fought", self.battles_fought)] { // stats can go below 0 from injuries, so they aren't checked
+            if v < 0 {
+                return Err(format!("{} cannot be negative (found {})", stat, v))
+            }
+        }
+        if self.battles_won > self.battles_fought {
+            return Err(format!("battles won ({}) cannot be more than battles fought ({})", self.battles_won, self.battles_fought))
+        }
+        Ok(())
+    }
+
     pub fn total(&self) -> i32 {
         self.strength + self.speed + self.skill + self.unspent_points
     }
 
     #[allow(unused_variables)]
-    pub fn injure(&mut self, arena: &Arena, modifier: &Modifier, batlog: &mut Batlog, will_injure: bool) -> Option<i32> {
+    #[allow(clippy::needless_return)]
+    pub fn injure(&mut self, arena: &Arena, modifier: &Modifier, batlog: &mut Batlog, will_injure: bool, rng: &mut impl Rng) -> Option<i32> {
         if !will_injure { // work this out in the battle method
             batlog.set_injury(None);
             return None
         }
 
-        let mut roll = if let Arena::Siphon = arena {thread_rng().gen_range(0..8)} 
+        let mut roll = if let Arena::Siphon = arena {rng.gen_range(0..8)} 
         else {
             match self.class {
                 Class::Tank => {
-                    utils::select_largest(thread_rng().gen_range(0..8), thread_rng().gen_range(0..8)) // best of 2 rolls
+                    utils::select_largest(rng.gen_range(0..8), rng.gen_range(0..8)) // best of 2 rolls
                 }
-                _ => thread_rng().gen_range(0..8) // no other classes affect injury rolls (yet)
+                _ => rng.gen_range(0..8) // no other classes affect injury rolls (yet)
             }
         };
         match modifier {
@@ -131,7 +206,8 @@ impl Fighter {
         return Some(roll)
     }
 
-    pub fn roll_for_stats(&self, arena: &Arena, modifier: &Modifier) -> Vec<i32> {
+    #[allow(clippy::match_single_binding, clippy::needless_range_loop)]
+    pub fn roll_for_stats(&self, arena: &Arena, modifier: &Modifier, rng: &mut impl Rng) -> Vec<i32> {
         let mut stats = vec![self.strength, self.speed, self.skill]; // easier to manupilate a vec later than an array
 
         match arena {
@@ -154,15 +230,12 @@ impl Fighter {
         if let Arena::Siphon = arena {} else { // no class effects if its siphon
             match self.class {
                 Class::Swarm => { // 2d5
-                    for i in 0..3 {
-                        stats[i] += thread_rng().gen_range(1..6) + thread_rng().gen_range(1..6);
-
-                        return stats;
-                    }
+                    stats[0] += rng.gen_range(1..6) + rng.gen_range(1..6); // strength only, speed and skill don't get a roll
+                    return stats;
                 }
                 Class::Mutant => { // random stat gets best of 2 rolls
-                    mutant_roll = thread_rng().gen_range(0..3);
-                    stats[mutant_roll] = utils::select_largest(thread_rng().gen_range(1..11), thread_rng().gen_range(1..11))
+                    mutant_roll = rng.gen_range(0..3);
+                    stats[mutant_roll] = utils::select_largest(rng.gen_range(1..11), rng.gen_range(1..11))
                 }
                 _ => {}
             }
@@ -171,7 +244,7 @@ impl Fighter {
 
         for i in 0..3 {
             if i != mutant_roll { // don't roll for the mutant stat
-                stats[i] += thread_rng().gen_range(1..11);
+                stats[i] += rng.gen_range(1..11);
             }
         }
 
@@ -194,6 +267,15 @@ impl Fighter {
     }
 }*/
 
+impl Class {
+    pub fn starting_points(&self) -> i32 { // what a new fighter gets to spread over their stats
+        match self {
+            Class::Naked => 13,
+            _ => 12
+        }
+    }
+}
+
 impl FromStr for Class { // much cleaner
     type Err = String;
 
@@ -224,3 +306,47 @@ impl fmt::Display for Class {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_swarm_rolls() {
+        let f = Fighter::new(String::from("bob"), String::from("al"), Class::Swarm, 4, 5, 6);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..20 {
+            let stats = f.roll_for_stats(&Arena::Ampitheater, &Modifier::Rulebook, &mut rng);
+            assert!((6..=14).contains(&stats[0]));
+            assert_eq!(&stats[1..], &[5, 6]);
+        }
+    }
+    #[test]
+    fn test_set_field() {
+        let mut f = Fighter::new(String::from("bob"), String::from("al"), Class::Cleric, 4, 4, 4);
+        assert!(f.set_field("class", "chicken").is_ok());
+        assert_eq!(f.class, Class::Tank);
+        assert!(f.set_field("rating", "-3").is_ok());
+        assert_eq!(f.get_field("rating"), Ok(String::from("-3")));
+        assert!(f.set_field("kills", "lots").is_err());
+        assert!(f.set_field("beans", "1").is_err());
+    }
+    #[test]
+    fn test_validate() {
+        let mut f = Fighter::new(String::from("bob"), String::from("al"), Class::Cleric, 4, 4, 4);
+        assert!(f.validate().is_ok());
+        f.battles_won = 1;
+        assert!(f.validate().is_err());
+        f.battles_fought = 1;
+        assert!(f.validate().is_ok());
+        f.strength = -2; // badly injured, but still a fighter
+        assert!(f.validate().is_ok());
+        f.kills = -1;
+        assert!(f.validate().is_err());
+        f.kills = 0;
+        f.name = String::new();
+        assert!(f.validate().is_err());
+    }
+}
@@ -1,22 +1,56 @@
 use serde::{Serialize, Deserialize};
 use std::fs;
-use pad::{PadStr, Alignment};
-use argparse::{ArgumentParser, StoreOption};
-use std::io::{stdout, stderr};
+use std::path::Path;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
 use super::fighter::*;
-use super::round::{GameRound, Arena, Modifier, Round};
-use super::battle::{battle, BattleResult};
-use super::utils::{ProgramOptions, fmt_vec, fmt_option, get_non_repeating_filename, fmt_vec_with_tabs};
+use super::round::{GameRound, Arena, Modifier, Pairing, Round};
+use super::battle::battle;
+use super::utils::{get_non_repeating_filename, timestamp};
+use super::edit::FighterEdit;
+use super::history::{HistoryEntry, MAX_HISTORY};
+use super::backup::{backup_file, BACKUPS_TO_KEEP};
+use super::migrate::{migrate_game, schema_version, GAME_SCHEMA_VERSION};
+use super::storage::storage_for;
+use super::check::{check_game, Problem};
+use super::journal::{Action, JournalEntry, SNAPSHOT_INTERVAL, read_journal, append_journal, archive_journal, journal_path};
+use super::config::Config;
+use super::error::GameError;
+use super::render::{Layout, join_chunks};
+use super::report::{Report, Outcome, RoundReport, MatchReport, FighterRef, FighterTable, FighterRow};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameState {
+    pub schema_version: u32,
+    pub generation: u64, // goes up by one every time the save is written, so concurrent edits can be caught
     pub fighters: Vec<Fighter>,
-    prev_rounds: Vec<Round>,
-    next_round: Option<Round>,
+    pub prev_rounds: Vec<Round>,
+    pub next_round: Option<Round>,
     pub num_rounds: i32,
     pub season_name: String,
-    pre_matches: Vec<(usize, usize)>, // indexes into fighters
+    pub pre_matches: Vec<(usize, usize)>, // indexes into fighters
+    #[serde(default)] // older saves don't have this
+    pub edits: Vec<FighterEdit>,
+    #[serde(default)]
+    history: Vec<HistoryEntry>, // oldest first
+    pub journal_mode: bool,
+    pub journal_len: usize, // number of journal entries this state includes
+
+    #[serde(skip)]
+    journal: Vec<JournalEntry>, // whole journal, including entries not yet written
+    #[serde(skip)]
+    journal_on_disk: usize, // how many entries of the journal were read from disk
+    #[serde(skip)]
+    snapshot_len: Option<usize>, // journal_len of the save file as loaded, None if it wasn't in journal mode
+    #[serde(skip)]
+    clock: Option<String>, // when the last command was applied. replays use the journal's time, so edits come out the same
+}
+
+pub struct Batch { // commands run since start_batch, see finish_batch
+    before: GameState,
+    history: Vec<HistoryEntry>,
+    journal_start: usize,
 }
 
 impl GameState {
@@ -25,12 +59,22 @@ impl GameState {
     pub fn new_game(season_name: &str) -> GameState {
         let season_name = season_name.to_string();
         GameState {
+            schema_version: GAME_SCHEMA_VERSION,
+            generation: 0,
             fighters: Vec::new(),
             prev_rounds: Vec::new(),
             next_round: None,
             num_rounds: 0,
             season_name,
-            pre_matches: Vec::new()
+            pre_matches: Vec::new(),
+            edits: Vec::new(),
+            history: Vec::new(),
+            journal_mode: false,
+            journal_len: 0,
+            journal: Vec::new(),
+            journal_on_disk: 0,
+            snapshot_len: None,
+            clock: None
         }
     }
 
@@ -38,12 +82,28 @@ impl GameState {
         self.fighters.push(f); // fighter creation is handled in the fighter module
     }
 
-    pub fn arrange_match(&mut self, f1i: usize, f2i: usize) -> Result<(), String> {
+    pub fn add_stats(&mut self, index: usize, stats: [i32; 3]) -> Result<(), GameError> {
+        if index >= self.fighters.len() {
+            return Err(GameError::FighterOutOfRange(index))
+        }
+        let f = &mut self.fighters[index];
+
+        f.strength += stats[0];
+        f.speed += stats[1];
+        f.skill += stats[2];
+        f.unspent_points -= stats.iter().sum::<i32>();
+        Ok(())
+    }
+
+    pub fn arrange_match(&mut self, f1i: usize, f2i: usize) -> Result<(), GameError> {
+        if let Some(i) = [f1i, f2i].into_iter().find(|i| *i >= self.fighters.len()) {
+            return Err(GameError::FighterOutOfRange(i))
+        }
         if self.fighters[f1i].dead || self.fighters[f2i].dead {
-            return Err(String::from("cannot prematch dead fighters!")) // self explanatory
+            return Err(GameError::Rule(String::from("cannot prematch dead fighters!"))) // self explanatory
         }
-        if let None = self.next_round {
-            return Err(String::from("cannot prematch while a round is scheduled!"))
+        if self.next_round.is_some() {
+            return Err(GameError::Rule(String::from("cannot prematch while a round is scheduled!")))
         }
         self.fighters[f1i].pre_matched = true; // avoid auto matching them later
         self.fighters[f2i].pre_matched = true;
@@ -51,245 +111,502 @@ impl GameState {
         Ok(())
     }
 
-    const TABLE_SEP: &'static str = " | "; // avoid magic numbers
-    pub fn list_fighters(&self) {
-        let mut longest_name_len: usize = 4; // length of "name"
-        let mut longest_owner_len: usize = 5; // length of "owner"
+    pub fn edit_fighter(&mut self, index: usize, changes: &[(String, String)], note: Option<String>) -> Result<Vec<FighterEdit>, GameError> {
+        if index >= self.fighters.len() {
+            return Err(GameError::FighterOutOfRange(index))
+        }
+        if changes.is_empty() {
+            return Err(GameError::Parse(String::from("no changes given")))
+        }
 
-        for f in &self.fighters { // get size of longest name for padding
-            let ln = f.name.len();
-            let lo = f.owner.len();
-            if ln > longest_name_len {
-                longest_name_len = ln
+        let mut f = self.fighters[index].clone(); // work on a copy so a bad edit doesnt leave things half done
+        let mut edits = Vec::new();
+        for (field, value) in changes {
+            let old = f.get_field(field).map_err(GameError::Parse)?;
+            f.set_field(field, value).map_err(GameError::Parse)?;
+            let new = f.get_field(field).map_err(GameError::Parse)?;
+            if old != new { // dont bother recording things that didnt change
+                edits.push(FighterEdit {
+                    time: self.now(),
+                    round: self.num_rounds,
+                    fighter: index,
+                    fighter_name: self.fighters[index].name.clone(),
+                    field: field.clone(),
+                    old, new,
+                    note: note.clone()
+                })
             }
-            if lo > longest_owner_len {
-                longest_owner_len = lo
+        }
+        f.validate().map_err(GameError::Parse)?;
+
+        self.fighters[index] = f;
+        self.edits.extend(edits.iter().cloned());
+        Ok(edits)
+    }
+
+    pub fn snapshot(&self) -> GameState { // copy of the game without its history, for undo
+        GameState {
+            history: Vec::new(),
+            journal: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    fn same_as(&self, other: &GameState) -> bool { // ignores history
+        serde_json::to_string(&self.snapshot()).ok() == serde_json::to_string(&other.snapshot()).ok()
+    }
+
+    fn record_history(&mut self, before: GameState, command: &str) { // call after a mutating command with the snapshot from before it
+        self.history.push(HistoryEntry {
+            time: timestamp(),
+            command: command.to_string(),
+            state: Box::new(before)
+        });
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+    }
+
+    pub fn undo(&mut self, n: usize) -> Result<Vec<String>, GameError> { // returns the commands that were undone, newest first
+        if n == 0 {
+            return Err(GameError::Rule(String::from("cannot undo 0 commands")))
+        }
+        if n > self.history.len() {
+            return Err(GameError::Rule(format!("cannot undo {} commands, only {} in history", n, self.history.len())))
+        }
+        let mut undone: Vec<HistoryEntry> = self.history.split_off(self.history.len() - n);
+        undone.reverse();
+        let commands: Vec<String> = undone.iter().map(|e| e.command.clone()).collect();
+
+        let remaining = std::mem::take(&mut self.history);
+        let target = undone.pop().unwrap(); // oldest undone entry is the state we want. n > 0 so this exists
+        let mut old = std::mem::replace(self, *target.state);
+        self.history = remaining;
+        self.keep_edits(&mut old, &commands);
+        self.keep_bookkeeping(old);
+        Ok(commands)
+    }
+
+    pub fn start_batch(&mut self) -> Batch { // for running several commands as one undo step, see finish_batch
+        Batch {
+            before: self.snapshot(),
+            history: std::mem::take(&mut self.history), // so the commands in the batch can't undo past its start
+            journal_start: self.journal.len()
+        }
+    }
+
+    pub fn finish_batch(&mut self, batch: Batch, command: &str) { // swaps everything since start_batch for one history entry and one journal entry
+        let entries: Vec<JournalEntry> = self.journal.drain(batch.journal_start..).enumerate().map(|(i, e)| JournalEntry { seq: i, ..e }).collect();
+        self.journal_len -= entries.len();
+        self.history = batch.history;
+        if !self.same_as(&batch.before) {
+            self.record_history(batch.before, command)
+        }
+        if !entries.is_empty() {
+            self.record(Action::Script { command: command.to_string(), entries })
+        }
+    }
+
+    fn keep_edits(&mut self, old: &mut GameState, commands: &[String]) { // the audit trail is append-only, so undo logs putting edited fields back instead of forgetting the edits
+        let since = self.edits.len(); // edits are never removed, so the restored trail is the start of the old one
+        let time = old.now(); // the restored state's clock is from back then
+        let mut reverted = Vec::new();
+        for e in &old.edits[since.min(old.edits.len())..] {
+            let (before, after) = match (old.fighters.get(e.fighter), self.fighters.get(e.fighter)) {
+                (Some(b), Some(a)) => (b, a),
+                _ => continue // the fighter was added by an undone command, so there's nothing left to edit
+            };
+            let (from, to) = (before.get_field(&e.field).unwrap_or_default(), after.get_field(&e.field).unwrap_or_default());
+            if from != to && !reverted.iter().any(|r: &FighterEdit| r.fighter == e.fighter && r.field == e.field) {
+                reverted.push(FighterEdit {
+                    time: time.clone(),
+                    round: self.num_rounds,
+                    fighter: e.fighter,
+                    fighter_name: before.name.clone(),
+                    field: e.field.clone(),
+                    old: from, new: to,
+                    note: Some(format!("undo {}", commands.join(", ")))
+                })
             }
         }
+        self.edits = std::mem::take(&mut old.edits);
+        self.edits.extend(reverted);
+    }
 
-        
-        let index_pad_amt = if self.fighters.len() > 0 {
-            (self.fighters.len() - 1).to_string().len() // ew
+    fn keep_bookkeeping(&mut self, old: GameState) { // after replacing the whole state, put back the things that track the file rather than the game
+        self.generation = old.generation; // undoing is still a new write as far as other gms are concerned
+        self.journal_mode = old.journal_mode;
+        self.journal_len = old.journal_len;
+        self.journal = old.journal;
+        self.journal_on_disk = old.journal_on_disk;
+        self.snapshot_len = old.snapshot_len;
+        self.clock = old.clock;
+    }
+
+    fn now(&self) -> String {
+        self.clock.clone().unwrap_or_else(timestamp)
+    }
+
+    pub fn apply(&mut self, action: &mut Action) -> Result<Outcome, GameError> { // runs a mutating command. says whether it changed anything and what happened
+        self.apply_at(action, timestamp())
+    }
+
+    fn apply_at(&mut self, action: &mut Action, time: String) -> Result<Outcome, GameError> {
+        self.clock = Some(time);
+        if let Action::Undo { n } = action { // undo doesnt go in the history itself
+            let commands = self.undo(*n)?;
+            return Ok(Outcome { changed: true, report: Report::Undone(commands) })
         }
-        else {
-            0usize
+
+        let before = self.snapshot();
+        let report = match action {
+            Action::Start { state } => {
+                let old = std::mem::replace(self, (**state).clone());
+                self.keep_bookkeeping(old);
+                self.journal_mode = true;
+                Report::Nothing
+            }
+            Action::AddFighter { fighter } => {
+                self.add_fighter(fighter.clone());
+                Report::Nothing
+            }
+            Action::AddFighters { fighters } => {
+                let first = self.fighters.len();
+                for f in fighters.iter() {
+                    self.add_fighter(f.clone())
+                }
+                Report::FightersAdded((first..self.fighters.len()).map(|i| self.fighter_ref(i)).collect())
+            }
+            Action::EditFighter { fighter, changes, note } => Report::Edited(self.edit_fighter(*fighter, changes, note.clone())?),
+            Action::AddStats { fighter, stats } => {
+                self.add_stats(*fighter, *stats)?;
+                Report::StatsAdded { fighter: self.fighters[*fighter].name.clone(), stats: *stats }
+            }
+            Action::ArrangeMatch { fighter_1, fighter_2 } => {
+                self.arrange_match(*fighter_1, *fighter_2)?;
+                Report::MatchArranged { fighter_1: self.fighter_ref(*fighter_1), fighter_2: self.fighter_ref(*fighter_2) }
+            }
+            Action::NewRound { arena, modifier, pairing, seed, round } => {
+                let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                let r = self.new_round(arena.clone(), modifier.clone(), *pairing, &mut rng)?;
+                check_outcome(round, self.next_round.as_ref())?;
+                Report::RoundScheduled(r)
+            }
+            Action::RunRound { seed, round } => {
+                let mut rng = ChaCha8Rng::seed_from_u64(*seed);
+                let r = self.run_round(&mut rng)?;
+                check_outcome(round, self.prev_rounds.last())?;
+                Report::RoundRun(r)
+            }
+            Action::CancelRound => Report::RoundCancelled(self.cancel_next_round()?),
+            Action::Repair => Report::Repaired(check_game(self, true)),
+            Action::Script { entries, .. } => { // same as start_batch, the history entry for the whole thing is added below
+                let history = std::mem::take(&mut self.history);
+                let result = entries.iter_mut().try_for_each(|e| self.apply_at(&mut e.action, e.time.clone()).map(|_| ()));
+                self.history = history;
+                result?;
+                Report::Nothing
+            }
+            Action::Undo { .. } => unreachable!() // handled above
         };
-        for _ in 0..(index_pad_amt + 3) { // adds spaces to pad the front of the headings
-            print!(" ") // a stupid solution to a stupid problem
-        }
-
-        let name_head = "name".pad_to_width(longest_name_len); // pad out headings
-        let owner_head = "owner".pad_to_width(longest_owner_len);
-
-        let headings = format!("{1}{0}{2}{0}class   {0}strength{0}speed{0}skill{0}points{0}total{0}rating{0}kills", Self::TABLE_SEP, name_head, owner_head);
-        // class is done lazily cuz it's a discrete thing
-        // class as string will never be longer than 8
-        // if this fact ever changes FIX THIS
-        println!("{}", headings);
-
-        for (i, f) in self.fighters.iter().enumerate() {
-            let name_pad = f.name.pad_to_width_with_alignment(longest_name_len, Alignment::Right); // pad out names etc
-            let owner_pad = f.owner.pad_to_width_with_alignment(longest_owner_len, Alignment::Right);
-            let class_pad = format!("{}", f.class).pad_to_width_with_alignment(8, Alignment::Right); // should possibly eliminate a magic number here
-            let index_pad = i.to_string().pad_to_width_with_alignment(index_pad_amt, Alignment::Right);
-
-            // name owner class st sp sk us tt rt kl
-            print!("[{9}] {1}{0}{2}{0}{3}{0}{4:>8}{0}{5:>5}{0}{6:>5}{0}{11:>6}{0}{10:>5}{0}{7:>6}{0}{8:>5}", 
-                Self::TABLE_SEP, name_pad, owner_pad, class_pad, f.strength, f.speed, f.skill, f.rating, f.kills, index_pad, f.total(), f.unspent_points); // the Worst format string
-            if f.dead {
-                print!("  (dead)")
+
+        let changed = !self.same_as(&before);
+        if changed { // eg an edit that sets things to what they already were doesnt count
+            self.record_history(before, &action.to_string())
+        }
+        Ok(Outcome { changed, report })
+    }
+
+    pub fn record(&mut self, action: Action) { // call after a successful apply to add it to the journal
+        if !self.journal_mode {
+            return
+        }
+        self.journal.push(JournalEntry {
+            seq: self.journal_len,
+            time: self.now(),
+            action
+        });
+        self.journal_len += 1;
+    }
+
+    pub fn start_journal(&mut self) -> Result<(), String> {
+        if self.journal_mode {
+            return Err(String::from("journal mode is already on"))
+        }
+        self.journal_mode = true;
+        self.journal_len = 0;
+        self.journal = Vec::new();
+        self.journal_on_disk = 0;
+        let state = Box::new(self.snapshot());
+        self.record(Action::Start { state });
+        Ok(())
+    }
+
+    pub fn stop_journal(&mut self) -> Result<(), String> {
+        if !self.journal_mode {
+            return Err(String::from("journal mode is already off"))
+        }
+        self.journal_mode = false;
+        Ok(())
+    }
+
+    pub fn verify_journal(&self) -> Result<(), GameError> { // rebuild the game from nothing but the journal and check it matches
+        if !self.journal_mode {
+            return Err(GameError::Rule(String::from("journal mode is off")))
+        }
+        let mut rebuilt = GameState::new_game(&self.season_name);
+        for e in &self.journal {
+            let mut action = e.action.clone();
+            if let Err(err) = rebuilt.apply_at(&mut action, e.time.clone()) {
+                return Err(GameError::Rule(format!("journal entry {} failed to replay ({})", e.seq, err)))
             }
-            println!("");
+        }
+        rebuilt.generation = self.generation;
+        rebuilt.journal_len = self.journal_len;
+        if rebuilt.same_as(self) {
+            Ok(())
+        }
+        else {
+            Err(GameError::Rule(String::from("replaying the journal does not give the current game!")))
         }
     }
-    fn format_round(&self, round: &Round) -> String {
-        let mut ret = String::new();
 
-        match round {
-            Round::Standard(r) => {
-                let round_run = r.log.fights.len() != 0; // check if the round is in the past
-                // i coulda done that with a bool but it would fuck up the existing test save (i am Very Lazy)
-
-                ret.push_str(&format!("round {}\narena: {}\nmodifier: {}\nmatchups:\n", r.log.round_no, r.arena, r.modifier));
-                let mut i = 0;
-                for matchup in &r.matchups {
-                    let (f1, f2) = *matchup;
-                    let f1name = &self.fighters[f1].name;
-                    let f2name = &self.fighters[f2].name;
-                    ret.push_str(&format!("\t{} VS {}\n", f1name, f2name));
-                    if round_run { // only log results if the round has been run. they don't exist otherwise
-                        let battle = &r.log.fights[i];
-                        ret.push_str(&format!("\t\trolls:\n\t\t\t{} VS {}\n", fmt_vec(&battle.rolls_1), fmt_vec(&battle.rolls_2)));
-                        ret.push_str(&format!("\t\tinjuries:\n\t\t\t{}: {}\n\t\t\t{}: {}\n", f1name, fmt_option(&battle.injury_1), f2name, fmt_option(&battle.injury_2))); // lotsa tabs
-                        ret.push_str(&format!("\t\tother events:\n{}", fmt_vec_with_tabs(&battle.other_events, 3)));
-
-                        type Res = BattleResult; // arguably makes code easier to read i guess???
-                        match battle.result {
-                            Res::F1Win | Res::F1WinFromCleric => {
-                                ret.push_str(&format!("\t\twinner: {}", f1name))
-                            }
-                            Res::F2Win | Res::F2WinFromCleric => {
-                                ret.push_str(&format!("\t\twinner: {}", f2name))
-                            }
-                            Res::Draw | Res::DrawFromCleric => {
-                                ret.push_str(&format!("\t\tdraw!"))
-                            }
-                        }
-                    }
-                    i += 1;
-                }
-                match r.sitting_out {
-                    Some(i) => {
-                        ret.push_str(&format!("\t{} sits out", self.fighters[i].name))
-                    }
-                    None => {}
-                }
+    fn replay_journal(&mut self, filename: &str) -> Result<(), GameError> { // apply the entries the save file doesnt include yet
+        self.journal = read_journal(filename).map_err(GameError::Io)?;
+        self.journal_on_disk = self.journal.len();
+        self.snapshot_len = Some(self.journal_len);
+        let start = self.journal_len;
+        let tail: Vec<JournalEntry> = self.journal.iter().filter(|e| e.seq >= start).cloned().collect();
+        for e in tail {
+            if e.seq != self.journal_len {
+                return Err(GameError::Conflict(format!("journal for {} skips from entry {} to entry {}", filename, self.journal_len, e.seq)))
+            }
+            let mut action = e.action.clone();
+            if let Err(err) = self.apply_at(&mut action, e.time.clone()) {
+                return Err(GameError::Conflict(format!("journal entry {} for {} failed to replay ({})", e.seq, filename, err)))
             }
-            Round::Boss(_) => unreachable!() // not implemented
+            self.journal_len += 1;
         }
+        Ok(())
+    }
+
+    pub fn check(&self) -> Vec<Problem> { // doesnt change anything, see Action::Repair for that
+        check_game(&mut self.clone(), false)
+    }
+
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    pub fn journal(&self) -> &[JournalEntry] {
+        &self.journal
+    }
+
+    pub fn fighter_ref(&self, index: usize) -> FighterRef {
+        FighterRef { index, name: self.fighters.get(index).map(|f| f.name.clone()).unwrap_or_default() }
+    }
 
-        ret
+    pub fn fighter_table(&self) -> FighterTable {
+        FighterTable { rows: self.fighters.iter().enumerate().map(|(i, f)| FighterRow::new(i, f)).collect() }
     }
-    fn log_round_priv(&self, r: &Round, po: &ProgramOptions, path: Option<&str>) {
+
+    pub fn report_round(&self, round: &Round) -> Result<RoundReport, GameError> {
+        match round {
+            Round::Standard(r) => Ok(RoundReport {
+                round_no: r.log.round_no,
+                arena: r.arena.clone(),
+                modifier: r.modifier.clone(),
+                run: r.run,
+                matchups: r.matchups.iter().enumerate().map(|(i, (f1, f2))| MatchReport {
+                    fighter_1: self.fighter_ref(*f1),
+                    fighter_2: self.fighter_ref(*f2),
+                    battle: if r.run { r.log.fights.get(i).cloned() } else { None } // results don't exist until it's run
+                }).collect(),
+                sitting_out: r.sitting_out.map(|i| self.fighter_ref(i))
+            }),
+            Round::Boss(_) => Err(GameError::Rule(String::from("boss rounds aren't supported yet"))) // not implemented
+        }
+    }
+
+    pub fn next_round_report(&self) -> Result<Option<RoundReport>, GameError> {
+        self.next_round.as_ref().map(|r| self.report_round(r)).transpose()
+    }
+
+    pub fn round_report(&self, number: usize) -> Result<RoundReport, GameError> {
+        match self.prev_rounds.get(number) {
+            Some(r) => self.report_round(r),
+            None => Err(GameError::RoundOutOfRange(number))
+        }
+    }
+
+    pub fn log_round(&self, number: usize, path: Option<&str>, layout: &Layout, config: &Config) -> Result<String, GameError> { // writes a round to a text file, and says where it went
+        let report = self.round_report(number)?;
         let filename = match path {
-            None => &po.global_data.default_batlog_name, // get template
+            None => &config.log_name, // get template
             Some(p) => p
         };
         let filename = filename.replace("%S", &self.season_name); // run replacements
-        let round_num = match r {
-            Round::Standard(v) => v.log.round_no,
-            Round::Boss(_) => unreachable!()
-        };
-        let filename = filename.replace("%R", &round_num.to_string());
+        let filename = filename.replace("%R", &report.round_no.to_string());
         let filename = filename.replace(" ", "_"); // not strictly necessary but fuck you
         //filename.push_str(".txt");
+        if path.is_none() && !config.log_dir.is_empty() && !Path::new(&config.log_dir).is_dir() { // relative to wherever this is run from, so it can only be checked now
+            return Err(GameError::Io(format!("log_dir {} is not a folder", config.log_dir)))
+        }
+        let filename = match path {
+            None => Path::new(&config.log_dir).join(filename).to_string_lossy().to_string(), // automatic logs go in log_dir
+            Some(_) => filename
+        };
         let filename = match get_non_repeating_filename(&filename) {
             Ok(v) => v,
             Err(v) => v
         };
-        match fs::write(&filename, self.format_round(r)) {
-            Ok(_) => {},
-            Err(_) => {
-                println!("failed to write file {}!", filename)
-            }
+        match fs::write(&filename, join_chunks(&layout.round(&report, &self.season_name))) {
+            Ok(_) => Ok(filename),
+            Err(_) => Err(GameError::Io(format!("failed to write file {}!", filename)))
         }
     }
-    pub fn display_next_round(&self) {
-        match &self.next_round { // only display if a round exists
-            Some(r) => println!("{}", self.format_round(&r)),
-            None => println!("no round scheduled") // exit without panicking
+
+    pub fn from_value(mut v: serde_json::Value) -> Result<(Self, u32), String> { // also returns the schema version it was upgraded from
+        let from = migrate_game(&mut v)?;
+        match serde_json::from_value::<GameState>(v) {
+            Ok(g) => Ok((g, from)),
+            Err(e) => Err(format!("json parse error ({})", e))
         }
     }
-    pub fn display_round(&self, number: usize) {
-        if self.prev_rounds.len() > number {
-            println!("{}", self.format_round(&self.prev_rounds[number]))
+
+    fn read_file(filename: &str, back_up_old: bool) -> Result<Self, GameError> { // the save as it is on disk, upgraded in memory
+        if !Path::new(filename).exists() {
+            return Err(GameError::NotFound(format!("save file {} is missing", filename)))
         }
-        else {
-            println!("index out of range!") // exit without panicking
+        let v = storage_for(filename).read(filename)?;
+        let from = schema_version(&v).map_err(|e| GameError::Parse(format!("{} for file {}", e, filename)))?;
+        if back_up_old && from < GAME_SCHEMA_VERSION { // keep the old version around, copied before any migration runs, in case one messes something up
+            let backup_name = format!("{}.v{}.bak", filename, from);
+            if !Path::new(&backup_name).exists() && fs::copy(filename, &backup_name).is_err() {
+                return Err(GameError::Io(format!("failed to back up {} before upgrading it", filename)))
+            }
         }
+        Self::from_value(v).map(|(g, _)| g).map_err(|e| GameError::Parse(format!("{} for file {}", e, filename)))
     }
-    pub fn log_round(&self, number: usize, path: Option<&str>, po: &ProgramOptions) {
-        if self.prev_rounds.len() > number {
-            self.log_round_priv(&self.prev_rounds[number], po, path)
+
+    pub fn load_from_file(filename: &str) -> Result<Self, GameError> {
+        match Self::read_file(filename, true) {
+            Ok(mut g) => {
+                if g.journal_mode {
+                    g.replay_journal(filename)?
+                }
+                Ok(g)
+            }
+            Err(e) => Err(e) // exit without panicking
         }
-        else {
-            println!("index out of range!") // exit without panicking
+    }
+    pub fn peek_file(filename: &str) -> Result<Self, GameError> { // load_from_file without touching anything: no .bak, no journal. journalled saves come back as of their last snapshot
+        Self::read_file(filename, false)
+    }
+    pub fn read_only(filename: &str) -> Result<Self, GameError> { // the game as load_from_file sees it, journal and all, but nothing is written (no .bak)
+        let mut g = Self::read_file(filename, false)?;
+        if g.journal_mode {
+            g.replay_journal(filename)? // only in memory
         }
+        Ok(g)
+    }
+    pub fn save_to_file(&self, filename: &str) -> Result<(), GameError> {
+        backup_file(filename, BACKUPS_TO_KEEP).map_err(GameError::Io)?;
+        storage_for(filename).write(filename, &serde_json::to_value(self).unwrap())
     }
 
-    pub fn load_from_file(filename: &str) -> Result<Self, String> {
-        match fs::read_to_string(filename) {
-            Ok(s) => {
-                match serde_json::from_str::<GameState>(&s) {
-                    Ok(g) => Ok(g),
-                    Err(e) => Err(format!("json parse error for file {} ({})", filename, e)) // exit without panicking
-                }
+    pub fn export(&self, filename: &str) -> Result<(), GameError> { // standalone copy of the game, in whatever format the filename asks for
+        let mut copy = self.snapshot();
+        copy.history = self.history.clone();
+        copy.generation = 0;
+        copy.journal_mode = false; // the journal stays with the original
+        copy.journal_len = 0;
+        storage_for(filename).write(filename, &serde_json::to_value(&copy).unwrap())
+    }
+
+    pub fn save_over(&mut self, filename: &str) -> Result<(), GameError> { // save_to_file, but refuses if someone else wrote the file since it was loaded
+        if !self.journal_mode && self.snapshot_len.is_none() {
+            return self.save_snapshot(filename)
+        }
+
+        if self.snapshot_len.is_none() { // journal just turned on, start fresh
+            archive_journal(filename).map_err(GameError::Io)?;
+        }
+        else { // make sure nobody else added to the journal
+            let disk_len = read_journal(filename).map_err(GameError::Io)?.len();
+            if disk_len != self.journal_on_disk {
+                return Err(GameError::Conflict(format!("{} was changed by someone else while this command was running (loaded {} entries, found {}). your changes have NOT been saved", journal_path(filename), self.journal_on_disk, disk_len)))
             }
-            Err(_) => Err(format!("file read error for file {}", filename)) // exit without panicking
+        }
+        append_journal(filename, &self.journal[self.journal_on_disk..]).map_err(GameError::Io)?;
+        self.journal_on_disk = self.journal.len();
+
+        let snapshot_due = match self.snapshot_len {
+            None => true,
+            Some(l) => self.journal_len - l >= SNAPSHOT_INTERVAL
+        };
+        if !self.journal_mode { // journal just turned off
+            self.save_snapshot(filename)?;
+            archive_journal(filename).map_err(GameError::Io)
+        }
+        else if snapshot_due {
+            self.save_snapshot(filename)?;
+            self.snapshot_len = Some(self.journal_len);
+            Ok(())
+        }
+        else {
+            Ok(())
         }
     }
-    pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
-        match fs::write(filename, serde_json::to_string_pretty(self).unwrap()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(format!("failed to write file {}", filename))
+
+    fn save_snapshot(&mut self, filename: &str) -> Result<(), GameError> {
+        let on_disk = storage_for(filename).read(filename)?;
+        if on_disk == serde_json::to_value(&*self).unwrap() { // nothing changed, so dont churn through backups
+            return Ok(())
         }
+        let disk_generation = on_disk.get("generation").and_then(|g| g.as_u64()).unwrap_or(0); // files from before the counter existed are generation 0
+        if disk_generation != self.generation {
+            return Err(GameError::Conflict(format!("{} was changed by someone else while this command was running (loaded generation {}, found generation {}). your changes have NOT been saved", filename, self.generation, disk_generation)))
+        }
+        self.generation += 1;
+        self.save_to_file(filename)
     }
 
     // game features
 
-    pub fn new_round(&mut self, po: &ProgramOptions, args: &mut Vec<String>) -> Result<(), String> { // generate round and store
+    pub fn new_round(&mut self, arena: Option<Arena>, modifier: Option<Modifier>, pairing: Pairing, rng: &mut impl Rng) -> Result<RoundReport, GameError> { // generate round and store
         //let (mut matchups, sitting_out) = generate_matchups(&self.fighters);
         //matchups.append(&mut self.pre_matches);
         //let mut round = GameRound::new(&self.fighters, &mut self.pre_matches, self.num_rounds + 1); // FIX THIS
 
-        let mut arena: Option<String> = None;
-        let mut modifier: Option<String> = None;
-        args.insert(0, String::from("new-round")); // argparse needs the name of the program/command as args[0] to work
-        
-        {
-            let mut ap = ArgumentParser::new();
-            ap.set_description("generates a new round in the current loaded game");
-            ap.refer(&mut arena).add_option(&["-a"], StoreOption, "choose an arena manually");
-            ap.refer(&mut modifier).add_option(&["-m"], StoreOption, "chose a modifier manually");
-            match ap.parse(args.clone(), &mut stdout(), &mut stderr()) {
-                Ok(_) => {},
-                Err(e) => match e {
-                    0 => {}
-                    _ => return Err(String::from("unknown argument parser error!"))
-                }
-            }
-        }
-        let arena_parsed = match arena {
-            Some(a) => {
-                match a.parse::<Arena>() {
-                    Ok(v) => Some(v),
-                    Err(e) => return Err(e)
-                }
-            }
-            None => None
-        };
-        let modifier_parsed = match modifier {
-            Some(m) => {
-                match m.parse::<Modifier>() {
-                    Ok(v) => Some(v),
-                    Err(e) => return Err(e)
-                }
-            }
-            None => None
-        };
-
-        let round = GameRound::new(&self.fighters, &mut self.pre_matches, self.num_rounds + 1, arena_parsed, modifier_parsed);
+        let round = GameRound::new(&self.fighters, &mut self.pre_matches, self.num_rounds + 1, arena, modifier, pairing, rng);
 
         let r = Round::Standard(round);
-
-        if po.verbosity > -1 {
-            println!("{}", self.format_round(&r))
-        }
+        let report = self.report_round(&r)?;
         self.next_round = Some(r);
 
-        Ok(())
+        Ok(report)
     }
-    pub fn cancel_next_round(&mut self, po: &ProgramOptions) {
-        if po.verbosity > -1 {
-            match self.next_round {
-                Some(_) => println!("cancelling round {}", self.num_rounds + 1),
-                None => println!("no scheduled round to cancel")
-            }
+    pub fn cancel_next_round(&mut self) -> Result<i32, GameError> { // returns the number of the round that was cancelled
+        match self.next_round.take() {
+            Some(_) => Ok(self.num_rounds + 1),
+            None => Err(GameError::NoRoundScheduled)
         }
-        self.next_round = None
     }
 
-    pub fn run_round(&mut self, po: &ProgramOptions) {
+    pub fn run_round(&mut self, rng: &mut impl Rng) -> Result<RoundReport, GameError> {
         let round = match &mut self.next_round { // check next round exists
             Some(r) => r,
-            None => {
-                println!("next round not yet generated!"); 
-                return // exit without panicking
-            }
+            None => return Err(GameError::NoRoundScheduled) // exit without panicking
         };
 
         match round {
             Round::Standard(r) => {
+                let n = self.fighters.len(); // a hand edited save can point anywhere, so check before anyone fights
+                if let Some(i) = r.matchups.iter().chain(self.pre_matches.iter()).flat_map(|(a, b)| [*a, *b]).find(|i| *i >= n) {
+                    return Err(GameError::FighterOutOfRange(i)) // stays scheduled, nothing has changed yet
+                }
                 for (f1i, f2i) in &r.matchups {
                     r.log.advance_to_next_battle(*f1i, *f2i);
                     let mut f1 = self.fighters[*f1i].clone(); // cant take 2 mut slices even though they don't overlap
@@ -297,24 +614,41 @@ impl GameState {
                     f1.pre_matched = false; // if you leave prematched on they wont get matched again next round
                     f2.pre_matched = false;
         
-                    battle(&mut f1, &mut f2, &r.arena, &r.modifier, &mut r.log);
+                    battle(&mut f1, &mut f2, &r.arena, &r.modifier, &mut r.log, rng);
         
                     self.fighters[*f1i] = f1; // put back into list
                     self.fighters[*f2i] = f2;
                 }
+                r.run = true;
         
                 self.prev_rounds.push(round.clone());
-                let r = &self.prev_rounds[self.num_rounds as usize]; // probably a better way to do this but the borrow checker gets angry if i use r from earlier
-                if po.verbosity > -1 {
-                    println!("{}", self.format_round(&r))
-                }
-                if po.logging {
-                    self.log_round_priv(&r, po, None)
-                }
             }
-            Round::Boss(_) => unreachable!()
+            Round::Boss(_) => return Err(GameError::Rule(String::from("boss rounds aren't supported yet"))) // stays scheduled, nothing has changed yet
         }
         self.next_round = None;
         self.num_rounds += 1;
+        self.report_round(&self.prev_rounds[self.prev_rounds.len() - 1])
+    }
+
+    pub fn preview_round(&self, seed: u64) -> Result<RoundReport, GameError> { // what RunRound with this seed will do, run on a copy so nothing here changes
+        let mut copy = self.clone();
+        copy.run_round(&mut ChaCha8Rng::seed_from_u64(seed)) // same rng as apply uses, so the real thing comes out identical
+    }
+}
+
+fn check_outcome(recorded: &mut Option<Round>, actual: Option<&Round>) -> Result<(), String> { // fills in the outcome of a random action, or checks it against the one from last time
+    match recorded {
+        None => {
+            *recorded = actual.cloned();
+            Ok(())
+        }
+        Some(r) => {
+            if serde_json::to_value(r).ok() == actual.and_then(|a| serde_json::to_value(a).ok()) {
+                Ok(())
+            }
+            else {
+                Err(String::from("replay gave a different result to the one recorded in the journal"))
+            }
+        }
     }
 }
@@ -1,39 +1,161 @@
 use serde::{Serialize, Deserialize};
 use std::fs;
+use std::path::Path;
 
 use crate::game;
+use crate::config::Config;
+use crate::error::GameError;
+use crate::hall::{HallOfFame, fingerprint};
+use crate::utils::{write_atomic, timestamp};
+use crate::migrate::{migrate_global, schema_version, GLOBAL_SCHEMA_VERSION};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GwGlobalData {
-    pub saves: Vec<String>,
-    pub default_batlog_name: String
+    pub schema_version: u32,
+    pub saves: Vec<SaveEntry>,
+    pub config: Config,
+    pub hall_of_fame: Option<HallOfFame>, // cache, thrown away when any save changes
 }
 impl Default for GwGlobalData {
     fn default() -> Self {
         GwGlobalData {
-            default_batlog_name: String::from("%S_batlog_%R.txt"),
-            saves: Vec::new()
+            schema_version: GLOBAL_SCHEMA_VERSION,
+            config: Config::default(),
+            saves: Vec::new(),
+            hall_of_fame: None
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SaveEntry {
+    pub name: String, // unique, used to pick the save on the command line
+    pub alias: Option<String>, // also unique, a second name for it
+    pub season_name: String,
+    pub path: String, // absolute
+    pub created: String,
+    pub last_modified: String,
+    pub archived: bool, // read-only, usually because the season is over
+}
+
+impl SaveEntry {
+    pub fn matches(&self, key: &str) -> bool {
+        self.name == key || self.alias.as_deref() == Some(key)
+    }
+}
+
+pub fn make_save_name(season_name: &str) -> String { // "Season 2: Electric Boogaloo" -> "season-2-electric-boogaloo"
+    let mut ret = String::new();
+    for c in season_name.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            ret.push(c)
+        }
+        else if !ret.is_empty() && !ret.ends_with('-') {
+            ret.push('-')
+        }
+    }
+    let ret = ret.trim_end_matches('-').to_string();
+    if ret.is_empty() {
+        String::from("save")
+    }
+    else {
+        ret
+    }
+}
+
 impl GwGlobalData {
-    #[allow(dead_code)] // shut up
-    pub fn add_save(&mut self, filename: &str) -> Result<(), String> {
-        match game::GameState::load_from_file(filename) {
-            Ok(_) => {
-                self.saves.push(String::from(filename));
-                Ok(())
+    pub fn add_save(&mut self, filename: &str, name: Option<&str>) -> Result<&SaveEntry, GameError> { // checks the save loads, then registers it
+        let path = match Path::new(filename).canonicalize() { // so the program can be run from anywhere
+            Ok(p) => p.to_string_lossy().to_string(),
+            Err(_) => return Err(GameError::NotFound(format!("could not find {}", filename)))
+        };
+        let game = game::GameState::load_from_file(filename)?;
+        if let Some(e) = self.saves.iter().find(|e| e.path == path) {
+            return Err(GameError::Rule(format!("{} is already registered as {}", path, e.name)))
+        }
+        let name = match name {
+            Some(n) => {
+                self.check_name(n)?;
+                n.to_string()
             }
-            Err(e) => Err(e)
+            None => self.unique_name(&make_save_name(&game.season_name))
+        };
+        let now = timestamp();
+        self.saves.push(SaveEntry {
+            name,
+            alias: None,
+            season_name: game.season_name,
+            path,
+            created: now.clone(),
+            last_modified: now,
+            archived: false
+        });
+        Ok(self.saves.last().unwrap())
+    }
+
+    pub fn unique_name(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut i = 2;
+        while self.saves.iter().any(|e| e.matches(&name)) {
+            name = format!("{}-{}", base, i);
+            i += 1
+        }
+        name
+    }
+
+    pub fn check_name(&self, name: &str) -> Result<(), String> { // for names and aliases picked by the user
+        if name.is_empty() || name.chars().any(|c| c.is_whitespace()) {
+            return Err(format!("\"{}\" is not a valid save name (no spaces allowed)", name))
         }
+        if name.starts_with('#') {
+            return Err(format!("save name {} can't start with #, that's for picking saves by index", name))
+        }
+        if let Some(e) = self.saves.iter().find(|e| e.matches(name)) {
+            return Err(format!("save name {} is already used by {}", name, e.path))
+        }
+        Ok(())
+    }
+
+    pub fn find_save(&self, key: &str) -> Result<usize, GameError> { // by name or alias, or #N for the index, so a save called 2 is never mistaken for #2
+        if let Some(i) = self.saves.iter().position(|e| e.matches(key)) {
+            return Ok(i)
+        }
+        match key.strip_prefix('#').map(|n| n.parse::<usize>()) {
+            Some(Ok(i)) if i < self.saves.len() => Ok(i),
+            Some(Ok(i)) => Err(GameError::SaveOutOfRange(i)),
+            _ => Err(GameError::NotFound(format!("no save called {} (use list-saves to see them, or #N for the Nth one)", key)))
+        }
+    }
+
+    pub fn hall_of_fame(&mut self, refresh: bool) -> &HallOfFame {
+        let fresh = match &self.hall_of_fame {
+            Some(h) => !refresh && h.fingerprint == fingerprint(&self.saves),
+            None => false
+        };
+        if !fresh {
+            self.hall_of_fame = Some(HallOfFame::compute(&self.saves))
+        }
+        self.hall_of_fame.as_ref().unwrap()
     }
 
+    #[allow(clippy::useless_format)]
     pub fn load_from_file(filename: &str) -> Result<Self, String> {
         match fs::read_to_string(filename) {
             Ok(s) => {
                 //println!("{}", s);
-                match serde_json::from_str(&s) {
+                let mut v = match serde_json::from_str::<serde_json::Value>(&s) {
+                    Ok(v) => v,
+                    Err(e) => return Err(format!("json parse failed: {}", e))
+                };
+                let from = schema_version(&v)?;
+                if from < GLOBAL_SCHEMA_VERSION { // same as save games, keep the old one from before migrating
+                    let backup_name = format!("{}.v{}.bak", filename, from);
+                    if !Path::new(&backup_name).exists() {
+                        write_atomic(&backup_name, &s)?
+                    }
+                }
+                migrate_global(&mut v)?;
+                match serde_json::from_value(v) {
                     Ok(v) => Ok(v),
                     Err(e) => {
                         Err(format!("json parse failed: {}", e))
@@ -47,9 +169,48 @@ impl GwGlobalData {
     }
 
     pub fn save_to_file(&self, filename: &str) -> Result<(), String> {
-        match fs::write(filename, serde_json::to_string_pretty(self).unwrap()) {
-            Ok(_) => Ok(()),
-            Err(_) => Err(format!("failed to write file {}", filename))
+        write_atomic(filename, serde_json::to_string_pretty(self).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, alias: Option<&str>) -> SaveEntry {
+        SaveEntry {
+            name: name.to_string(),
+            alias: alias.map(String::from),
+            season_name: String::new(),
+            path: format!("/tmp/{}.json", name),
+            created: String::new(),
+            last_modified: String::new(),
+            archived: false
         }
     }
+
+    #[test]
+    fn test_save_names() {
+        assert_eq!(make_save_name("Season 2: Electric Boogaloo"), "season-2-electric-boogaloo");
+        assert_eq!(make_save_name("2021"), "2021");
+        assert_eq!(make_save_name("!!"), "save");
+
+        let mut g = GwGlobalData::default();
+        g.saves.push(entry("spring", Some("current")));
+        g.saves.push(entry("summer", None));
+        assert_eq!(g.unique_name("spring"), "spring-2");
+        assert_eq!(g.find_save("current"), Ok(0));
+        assert_eq!(g.find_save("summer"), Ok(1));
+        assert_eq!(g.find_save("#1"), Ok(1));
+        assert_eq!(g.find_save("#2"), Err(GameError::SaveOutOfRange(2)));
+        assert!(matches!(g.find_save("1"), Err(GameError::NotFound(_)))); // bare numbers are names
+        g.saves.push(entry("0", None));
+        assert_eq!(g.find_save("0"), Ok(2));
+        assert_eq!(g.find_save("#0"), Ok(0));
+        assert!(g.check_name("current").is_err());
+        assert!(g.check_name("has space").is_err());
+        assert!(g.check_name("#3").is_err());
+        assert!(g.check_name("3").is_ok());
+        assert!(g.check_name("autumn").is_ok());
+    }
 }
@@ -0,0 +1,280 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::UNIX_EPOCH;
+use std::cmp::Reverse;
+
+use crate::game::GameState;
+use crate::global::SaveEntry;
+use crate::round::Round;
+use crate::battle::BattleResult;
+use crate::journal::journal_path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Career { // one fighter, followed through every season they were carried over into
+    pub name: String, // as of their latest season
+    pub owner: String,
+    pub seasons: Vec<String>,
+    pub kills: i32,
+    pub battles_won: i32,
+    pub battles_fought: i32,
+    pub peak_rating: i32,
+    pub rounds_survived: i32,
+    pub championships: i32,
+    pub dead: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HallOfFame {
+    pub fingerprint: Vec<(String, String)>, // save path and when it (or its journal) last changed
+    pub careers: Vec<Career>,
+    pub skipped: Vec<String>, // saves that couldn't be read
+}
+
+#[derive(Debug, Default, Clone)]
+struct SeasonStats { // one fighter in one season, worked out from the batlogs
+    kills: i32,
+    won: i32,
+    fought: i32,
+    peak_rating: i32,
+    rating: i32,
+    rounds_survived: i32,
+}
+
+fn season_stats(game: &GameState) -> Vec<SeasonStats> {
+    let mut stats = vec![SeasonStats::default(); game.fighters.len()];
+    fn get(stats: &mut Vec<SeasonStats>, i: usize) { // bad indexes in old saves shouldn't crash this
+        if i >= stats.len() {
+            stats.resize(i + 1, SeasonStats::default())
+        }
+    }
+    for r in &game.prev_rounds {
+        let r = match r {
+            Round::Standard(r) if r.run => r,
+            _ => continue
+        };
+        let mut died: Vec<usize> = Vec::new();
+        for b in &r.log.fights {
+            get(&mut stats, b.fighter_1.max(b.fighter_2));
+            let (f1, f2) = (b.fighter_1, b.fighter_2);
+            stats[f1].fought += 1;
+            stats[f2].fought += 1;
+
+            let (winner, loser) = match b.result { // same rating changes as battle()
+                BattleResult::F1Win | BattleResult::F1WinFromCleric => (Some(f1), Some(f2)),
+                BattleResult::F2Win | BattleResult::F2WinFromCleric => (Some(f2), Some(f1)),
+                _ => (None, None)
+            };
+            if let (Some(w), Some(l)) = (winner, loser) {
+                let rdiff = stats[l].rating - stats[w].rating;
+                let change = if rdiff > 3 { 2 } else if rdiff < -3 { 0 } else { 1 };
+                stats[w].rating += change;
+                stats[l].rating -= change;
+                stats[w].won += 1;
+                stats[w].peak_rating = stats[w].peak_rating.max(stats[w].rating);
+            }
+
+            if matches!(b.injury_1, Some(i) if i < 1) {
+                stats[f2].kills += 1;
+                died.push(f1)
+            }
+            if matches!(b.injury_2, Some(i) if i < 1) {
+                stats[f1].kills += 1;
+                died.push(f2)
+            }
+        }
+        let in_round = r.matchups.iter().flat_map(|m| [m.0, m.1]).chain(r.sitting_out);
+        for i in in_round {
+            get(&mut stats, i);
+            if !died.contains(&i) {
+                stats[i].rounds_survived += 1
+            }
+        }
+    }
+    for (s, f) in stats.iter_mut().zip(&game.fighters) { // the replay starts everyone at 0, so carried over ratings can be higher
+        s.peak_rating = s.peak_rating.max(f.rating)
+    }
+    stats
+}
+
+fn champion(game: &GameState, stats: &[SeasonStats]) -> Option<usize> { // most wins, then rating, then kills
+    (0..game.fighters.len())
+        .filter(|i| stats[*i].fought > 0)
+        .max_by_key(|i| (stats[*i].won, game.fighters[*i].rating, stats[*i].kills))
+}
+
+pub fn fingerprint(saves: &[SaveEntry]) -> Vec<(String, String)> {
+    fn modified(path: &str) -> String {
+        match fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(t) => match t.duration_since(UNIX_EPOCH) {
+                Ok(d) => format!("{}.{:09}", d.as_secs(), d.subsec_nanos()),
+                Err(_) => String::from("?")
+            }
+            Err(_) => String::from("missing")
+        }
+    }
+    saves.iter().map(|s| {
+        (s.path.clone(), format!("{}/{}", modified(&s.path), modified(&journal_path(&s.path))))
+    }).collect()
+}
+
+impl HallOfFame {
+    pub fn compute(saves: &[SaveEntry]) -> Self {
+        let mut games: Vec<(&SaveEntry, GameState)> = Vec::new();
+        let mut skipped = Vec::new();
+        for s in saves {
+            match GameState::read_only(&s.path) { // just reading, so no migration backups, but journalled saves still count every command
+                Ok(g) => games.push((s, g)),
+                Err(e) => skipped.push(format!("{} ({})", s.name, e))
+            }
+        }
+        let by_path: HashMap<&str, usize> = games.iter().enumerate().map(|(i, (s, _))| (s.path.as_str(), i)).collect();
+
+        let root = |mut game: usize, mut fighter: usize| -> (usize, usize) { // follow carry over links back to the first season we know about
+            let mut steps = 0;
+            while let Some(link) = games[game].1.fighters.get(fighter).and_then(|f| f.previous.as_ref()) {
+                match by_path.get(link.path.as_str()) {
+                    Some(g) if steps < games.len() => {
+                        game = *g;
+                        fighter = link.fighter;
+                        steps += 1
+                    }
+                    _ => break
+                }
+            }
+            (game, fighter)
+        };
+
+        let mut careers: Vec<Career> = Vec::new();
+        let mut index: HashMap<(usize, usize), usize> = HashMap::new();
+        for (gi, (s, game)) in games.iter().enumerate() {
+            let stats = season_stats(game);
+            let champ = if s.archived { champion(game, &stats) } else { None }; // seasons still going don't have a winner yet
+            for (fi, f) in game.fighters.iter().enumerate() {
+                let ci = *index.entry(root(gi, fi)).or_insert_with(|| {
+                    careers.push(Career::default());
+                    careers.len() - 1
+                });
+                let c = &mut careers[ci];
+                let st = &stats[fi];
+                c.name = f.name.clone();
+                c.owner = f.owner.clone();
+                c.seasons.push(game.season_name.clone());
+                c.kills += st.kills;
+                c.battles_won += st.won;
+                c.battles_fought += st.fought;
+                c.peak_rating = c.peak_rating.max(st.peak_rating);
+                c.rounds_survived += st.rounds_survived;
+                c.dead |= f.dead;
+                if champ == Some(fi) {
+                    c.championships += 1
+                }
+            }
+        }
+
+        HallOfFame {
+            fingerprint: fingerprint(saves),
+            careers,
+            skipped
+        }
+    }
+
+    pub fn owner_championships(&self) -> Vec<(String, i32)> {
+        let mut owners: Vec<(String, i32)> = Vec::new();
+        for c in self.careers.iter().filter(|c| c.championships > 0) {
+            match owners.iter_mut().find(|o| o.0 == c.owner) {
+                Some(o) => o.1 += c.championships,
+                None => owners.push((c.owner.clone(), c.championships))
+            }
+        }
+        owners.sort_by_key(|o| Reverse(o.1));
+        owners
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::round::{GameRound, Pairing};
+    use crate::batlog::Batlog;
+    use crate::journal::Action;
+    use crate::testing::{season, temp_path};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn one_kill() -> GameState { // a beats b and kills them, c sits out
+        let mut game = season("test", &["a", "b", "c"]);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut r = GameRound::new(&game.fighters, &mut Vec::new(), 1, None, None, Pairing::Random, &mut rng);
+        r.matchups = vec![(0, 1)];
+        r.sitting_out = Some(2);
+        r.run = true;
+        r.log = Batlog::new(1);
+        r.log.advance_to_next_battle(0, 1);
+        r.log.set_result(BattleResult::F1Win);
+        r.log.fights[0].injury_2 = Some(0);
+        game.prev_rounds.push(Round::Standard(r));
+        game
+    }
+
+    #[test]
+    fn test_season_stats() {
+        let stats = season_stats(&one_kill());
+        assert_eq!((stats[0].kills, stats[0].won, stats[0].fought, stats[0].peak_rating, stats[0].rounds_survived), (1, 1, 1, 1, 1));
+        assert_eq!((stats[1].kills, stats[1].won, stats[1].fought, stats[1].rating, stats[1].rounds_survived), (0, 0, 1, -1, 0));
+        assert_eq!(stats[2].rounds_survived, 1); // sitting out still counts
+    }
+
+    #[test]
+    fn test_champion() {
+        let game = one_kill();
+        assert_eq!(champion(&game, &season_stats(&game)), Some(0));
+    }
+
+    #[test]
+    fn test_compute_only_reads() {
+        let game = season("old", &["a"]);
+        let mut v = serde_json::to_value(&game).unwrap();
+        v["schema_version"] = serde_json::json!(3); // from before carry over links
+        v["fighters"][0].as_object_mut().unwrap().remove("previous");
+        let path = format!("{}.json", temp_path("hall"));
+        let text = v.to_string();
+        fs::write(&path, &text).unwrap();
+
+        let entry = SaveEntry { name: String::from("old"), alias: None, season_name: String::from("old"), path: path.clone(),
+            created: String::new(), last_modified: String::new(), archived: false };
+        let hall = HallOfFame::compute(&[entry]);
+        let backed_up = std::path::Path::new(&format!("{}.v3.bak", path)).exists();
+        let untouched = fs::read_to_string(&path).unwrap() == text;
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(format!("{}.v3.bak", path));
+        assert!(hall.skipped.is_empty());
+        assert_eq!(hall.careers[0].name, "a");
+        assert!(!backed_up && untouched);
+    }
+
+    #[test]
+    fn test_journalled_season() { // rounds after the last snapshot only exist in the journal
+        let path = format!("{}.json", temp_path("hall_journal"));
+        let mut game = season("journal", &["a", "b"]);
+        game.save_to_file(&path).unwrap();
+        game.start_journal().unwrap();
+        game.save_over(&path).unwrap();
+        for mut action in [Action::NewRound { arena: None, modifier: None, pairing: Pairing::Random, seed: 7, round: None }, Action::RunRound { seed: 8, round: None }] {
+            game.apply(&mut action).unwrap();
+            game.record(action)
+        }
+        game.save_over(&path).unwrap();
+        assert!(GameState::peek_file(&path).unwrap().prev_rounds.is_empty()); // the save file itself is still from before the round
+
+        let entry = SaveEntry { name: String::from("journal"), alias: None, season_name: String::from("journal"), path: path.clone(),
+            created: String::new(), last_modified: String::new(), archived: false };
+        let hall = HallOfFame::compute(&[entry]);
+        for p in [vec![path.clone(), crate::journal::journal_path(&path)], crate::backup::list_backups(&path)].concat() {
+            let _ = fs::remove_file(p);
+        }
+        assert!(hall.skipped.is_empty());
+        assert_eq!(hall.careers.iter().map(|c| c.battles_fought).sum::<i32>(), 2);
+        assert_eq!(hall.careers.iter().map(|c| c.battles_won).sum::<i32>(), game.fighters.iter().map(|f| f.battles_won).sum::<i32>());
+    }
+}
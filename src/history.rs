@@ -0,0 +1,81 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use crate::game::GameState;
+
+pub const MAX_HISTORY: usize = 10; // every entry is a full copy of the save, so dont keep too many
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HistoryEntry { // the state of the game before a mutating command was run
+    pub time: String,
+    pub command: String,
+    pub state: Box<GameState>, // history of this one is always empty
+}
+
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] {}", self.time, self.command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighter::{Fighter, Class};
+    use crate::journal::Action;
+    use crate::error::GameError;
+
+    fn edit(game: &mut GameState, field: &str, value: &str) -> Result<(), GameError> {
+        game.apply(&mut Action::EditFighter { fighter: 0, changes: vec![(field.to_string(), value.to_string())], note: None }).map(|_| ())
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut game = GameState::new_game("undo");
+        assert!(matches!(game.undo(1), Err(GameError::Rule(_)))); // nothing to undo yet
+        game.apply(&mut Action::AddFighter { fighter: Fighter::new(String::from("bob"), String::from("al"), Class::Mutant, 4, 4, 4) }).unwrap();
+        for kills in 1..=3 {
+            edit(&mut game, "kills", &kills.to_string()).unwrap();
+        }
+        assert_eq!(game.history().len(), 4);
+
+        let undone = game.undo(2).unwrap();
+        assert_eq!(undone, vec!["edit-fighter 0 --kills 3", "edit-fighter 0 --kills 2"]); // newest first
+        assert_eq!((game.fighters[0].kills, game.history().len()), (1, 2));
+        assert!(game.undo(0).is_err() && game.undo(3).is_err());
+        assert_eq!(game.fighters[0].kills, 1); // a failed undo changes nothing
+    }
+
+    #[test]
+    fn test_undo_keeps_edits() {
+        let mut game = GameState::new_game("undo");
+        game.apply(&mut Action::AddFighter { fighter: Fighter::new(String::from("bob"), String::from("al"), Class::Mutant, 4, 4, 4) }).unwrap();
+        edit(&mut game, "owner", "cy").unwrap();
+        edit(&mut game, "owner", "di").unwrap();
+        game.undo(1).unwrap();
+        game.undo(1).unwrap();
+        assert_eq!(game.fighters[0].owner, "al");
+        let trail: Vec<(String, String, bool)> = game.edits.iter().map(|e| (e.old.clone(), e.new.clone(), e.note.is_some())).collect();
+        assert_eq!(trail, vec![ // both edits are still there, followed by the undos putting them back
+            (String::from("al"), String::from("cy"), false),
+            (String::from("cy"), String::from("di"), false),
+            (String::from("di"), String::from("cy"), true),
+            (String::from("cy"), String::from("al"), true)
+        ]);
+        assert!(matches!(edit(&mut game, "kills", "lots"), Err(GameError::Parse(_)))); // exit code 2, like before the journal
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut game = GameState::new_game("undo");
+        game.apply(&mut Action::AddFighter { fighter: Fighter::new(String::from("bob"), String::from("al"), Class::Mutant, 4, 4, 4) }).unwrap();
+        for kills in 1..=(MAX_HISTORY as i32 + 5) {
+            edit(&mut game, "kills", &kills.to_string()).unwrap();
+        }
+        assert_eq!(game.history().len(), MAX_HISTORY);
+        assert_eq!(game.history()[0].command, "edit-fighter 0 --kills 6"); // the oldest ones are dropped
+        game.undo(MAX_HISTORY).unwrap();
+        assert_eq!((game.fighters[0].kills, game.fighters.len()), (5, 1));
+        assert!(game.undo(1).is_err());
+    }
+}
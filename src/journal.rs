@@ -0,0 +1,249 @@
+use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::game::GameState;
+use crate::fighter::Fighter;
+use crate::round::{Arena, Modifier, Pairing, Round};
+
+pub const SNAPSHOT_INTERVAL: usize = 20; // how many journal entries between rewrites of the save file
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Action { // every command that changes a game
+    Start { state: Box<GameState> }, // first entry of every journal, the game as it was when journalling was turned on
+    AddFighter { fighter: Fighter },
+    AddFighters { fighters: Vec<Fighter> }, // import-fighters, so the whole sheet is one step to undo
+    EditFighter { fighter: usize, changes: Vec<(String, String)>, note: Option<String> },
+    AddStats { fighter: usize, stats: [i32; 3] },
+    ArrangeMatch { fighter_1: usize, fighter_2: usize },
+    NewRound { arena: Option<Arena>, modifier: Option<Modifier>, #[serde(default)] pairing: Pairing, seed: u64, round: Option<Round> }, // round is filled in with the outcome once it's been run
+    RunRound { seed: u64, round: Option<Round> },
+    CancelRound,
+    Undo { n: usize },
+    Repair,
+    Script { command: String, entries: Vec<JournalEntry> }, // run-script, so the whole script is one step to undo. seq counts from 0 within the script
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::Start { state } => write!(f, "start journal ({} rounds played)", state.num_rounds),
+            Action::AddFighter { fighter } => write!(f, "add-fighter {} {} {} {} {} {}", fighter.name, fighter.owner, fighter.class, fighter.strength, fighter.speed, fighter.skill),
+            Action::AddFighters { fighters } => write!(f, "import-fighters ({})", fighters.iter().map(|f| f.name.clone()).collect::<Vec<String>>().join(", ")),
+            Action::EditFighter { fighter, changes, note } => {
+                write!(f, "edit-fighter {}", fighter)?;
+                for (field, value) in changes {
+                    write!(f, " --{} {}", field, value)?
+                }
+                if let Some(n) = note {
+                    write!(f, " --note {}", n)?
+                }
+                Ok(())
+            }
+            Action::AddStats { fighter, stats } => write!(f, "add-stats {} {} {} {}", fighter, stats[0], stats[1], stats[2]),
+            Action::ArrangeMatch { fighter_1, fighter_2 } => write!(f, "arrange-match {} {}", fighter_1, fighter_2),
+            Action::NewRound { arena, modifier, pairing, .. } => {
+                write!(f, "new-round")?;
+                if let Some(a) = arena {
+                    write!(f, " -a {}", a)?
+                }
+                if let Some(m) = modifier {
+                    write!(f, " -m {}", m)?
+                }
+                if *pairing != Pairing::Random {
+                    write!(f, " -p {}", pairing)?
+                }
+                Ok(())
+            }
+            Action::RunRound { .. } => write!(f, "run-round"),
+            Action::CancelRound => write!(f, "cancel-round"),
+            Action::Undo { n } => write!(f, "undo {}", n),
+            Action::Repair => write!(f, "check --repair"),
+            Action::Script { command, .. } => write!(f, "{}", command),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JournalEntry {
+    pub seq: usize, // position in the journal, starting at 0
+    pub time: String,
+    pub action: Action,
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}] [{}] {}", self.seq, self.time, self.action)
+    }
+}
+
+pub fn journal_path(save_path: &str) -> String {
+    format!("{}.journal", save_path)
+}
+
+pub fn read_journal(save_path: &str) -> Result<Vec<JournalEntry>, String> { // one json object per line
+    let path = journal_path(save_path);
+    if !Path::new(&path).exists() {
+        return Ok(Vec::new())
+    }
+    let contents = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(_) => return Err(format!("file read error for file {}", path))
+    };
+    let mut ret = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue
+        }
+        match serde_json::from_str::<JournalEntry>(line) {
+            Ok(e) => ret.push(e),
+            Err(e) => return Err(format!("json parse error on line {} of {} ({})", i + 1, path, e))
+        }
+    }
+    Ok(ret)
+}
+
+pub fn append_journal(save_path: &str, entries: &[JournalEntry]) -> Result<(), String> {
+    let path = journal_path(save_path);
+    let mut buf = String::new();
+    for e in entries {
+        buf.push_str(&serde_json::to_string(e).unwrap());
+        buf.push('\n')
+    }
+    let result = OpenOptions::new().create(true).append(true).open(&path)
+        .and_then(|mut f| {
+            f.write_all(buf.as_bytes())?;
+            f.sync_all() // the journal is the real save in journal mode, so make sure it's on disk
+        });
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("failed to write file {} ({})", path, e))
+    }
+}
+
+pub fn archive_journal(save_path: &str) -> Result<(), String> { // moves an old journal out of the way instead of deleting it
+    let path = journal_path(save_path);
+    if !Path::new(&path).exists() {
+        return Ok(())
+    }
+    let archived = format!("{}.{}", path, chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    match fs::rename(&path, &archived) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("failed to move {} to {} ({})", path, archived, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighter::Class;
+    use crate::error::GameError;
+
+    fn temp_save(name: &str) -> String { // a fresh path, with anything left over from an earlier run cleared away
+        let path = std::env::temp_dir().join(format!("gw_journal_test_{}_{}.json", name, std::process::id())).to_string_lossy().to_string();
+        for p in [path.clone(), journal_path(&path)] {
+            let _ = fs::remove_file(p);
+        }
+        path
+    }
+
+    fn run(game: &mut GameState, mut action: Action) { // what cli::apply does
+        game.apply(&mut action).unwrap();
+        game.record(action)
+    }
+
+    fn journalled_game(path: &str) -> GameState { // saved with journal mode on, then 4 fighters and a round that's been run
+        let mut game = GameState::new_game("journal");
+        game.save_to_file(path).unwrap();
+        game.start_journal().unwrap();
+        game.save_over(path).unwrap();
+        for n in ["a", "b", "c", "d"] {
+            run(&mut game, Action::AddFighter { fighter: Fighter::new(n.to_string(), String::from("o"), Class::Mutant, 4, 4, 4) });
+        }
+        run(&mut game, Action::NewRound { arena: None, modifier: None, pairing: Pairing::Random, seed: 7, round: None });
+        run(&mut game, Action::RunRound { seed: 8, round: None });
+        game.save_over(path).unwrap();
+        game
+    }
+
+    fn on_disk(path: &str) -> GameState { // the save file by itself, without replaying the journal
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_replay_matches_saved_state() {
+        let path = temp_save("replay");
+        let game = journalled_game(&path);
+        assert_eq!(on_disk(&path).journal_len, 1); // only the start entry has been snapshotted, the rest is in the journal
+        assert_eq!(read_journal(&path).unwrap().len(), 7);
+
+        let loaded = GameState::load_from_file(&path).unwrap();
+        assert_eq!(serde_json::to_value(loaded.snapshot()).unwrap(), serde_json::to_value(game.snapshot()).unwrap());
+        assert!(loaded.verify_journal().is_ok());
+    }
+
+    #[test]
+    fn test_replay_edits() {
+        let path = temp_save("edits");
+        let mut game = journalled_game(&path);
+        run(&mut game, Action::EditFighter { fighter: 0, changes: vec![(String::from("owner"), String::from("cy"))], note: None });
+        game.save_over(&path).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(1100)); // so a replay would stamp the edit with a different time
+        assert!(game.verify_journal().is_ok());
+        let loaded = GameState::load_from_file(&path).unwrap();
+        assert_eq!(loaded.edits[0].time, game.edits[0].time);
+    }
+
+    #[test]
+    fn test_verify_catches_tampered_round() {
+        let path = temp_save("tamper");
+        let mut game = journalled_game(&path);
+        assert!(game.verify_journal().is_ok());
+        if let Some(Round::Standard(r)) = game.prev_rounds.last_mut() {
+            r.log.fights[0].points += 1
+        }
+        assert!(matches!(game.verify_journal(), Err(GameError::Rule(_))));
+    }
+
+    #[test]
+    fn test_snapshot_interval() {
+        let path = temp_save("snapshot");
+        let mut game = journalled_game(&path);
+        let start = on_disk(&path).journal_len;
+        while game.journal_len - start < SNAPSHOT_INTERVAL - 1 {
+            run(&mut game, Action::AddStats { fighter: 0, stats: [0, 0, 0] });
+            game.save_over(&path).unwrap();
+            assert_eq!(on_disk(&path).journal_len, start); // not due yet
+        }
+        run(&mut game, Action::AddStats { fighter: 0, stats: [0, 0, 0] });
+        game.save_over(&path).unwrap();
+        assert_eq!(on_disk(&path).journal_len, start + SNAPSHOT_INTERVAL);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let path = temp_save("conflict");
+        journalled_game(&path);
+
+        let mut first = GameState::load_from_file(&path).unwrap();
+        let mut second = GameState::load_from_file(&path).unwrap();
+        run(&mut first, Action::AddStats { fighter: 0, stats: [1, 0, 0] });
+        first.save_over(&path).unwrap();
+        run(&mut second, Action::AddStats { fighter: 1, stats: [1, 0, 0] });
+        assert!(matches!(second.save_over(&path), Err(GameError::Conflict(_)))); // the journal grew since second loaded it
+
+        let mut entries = read_journal(&path).unwrap(); // a round whose recorded outcome doesn't match its seed
+        for e in entries.iter_mut() {
+            if let Action::RunRound { seed, .. } = &mut e.action {
+                *seed += 1
+            }
+        }
+        fs::remove_file(journal_path(&path)).unwrap();
+        append_journal(&path, &entries).unwrap();
+        let e = GameState::load_from_file(&path).unwrap_err();
+        assert!(e.to_string().contains("different result"), "{}", e);
+    }
+}
@@ -0,0 +1,34 @@
+// the gladiator war engine. nothing in here prints anything: operations hand back data (see report) and errors (see error),
+// and it's up to whatever's on top (the cli in main.rs, a bot, a web viewer...) to show them
+
+pub mod game;
+pub mod fighter;
+pub mod utils;
+pub mod batlog;
+pub mod global;
+pub mod round;
+pub mod battle;
+pub mod boss;
+pub mod edit;
+pub mod history;
+pub mod backup;
+pub mod migrate;
+pub mod lock;
+pub mod journal;
+pub mod storage;
+pub mod check;
+pub mod season;
+pub mod hall;
+pub mod config;
+pub mod output;
+pub mod error;
+pub mod report;
+pub mod script;
+pub mod render;
+pub mod publish;
+pub mod template;
+pub mod csv;
+pub mod signup;
+
+#[cfg(test)]
+mod testing;
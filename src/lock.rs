@@ -0,0 +1,60 @@
+use std::fs::{File, OpenOptions, TryLockError};
+
+use crate::error::GameError;
+
+pub struct SaveLock { // holds an advisory lock on a save game until it's dropped
+    _file: File,
+}
+
+impl SaveLock {
+    pub fn acquire(save_path: &str) -> Result<SaveLock, GameError> {
+        let lock_path = format!("{}.lock", save_path); // separate file, since saves get renamed over when written
+        let file = match OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path) {
+            Ok(f) => f,
+            Err(e) => return Err(GameError::Io(format!("failed to open lock file {} ({})", lock_path, e)))
+        };
+        match file.try_lock() {
+            Ok(_) => Ok(SaveLock { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(GameError::Conflict(format!("{} is being used by another gladiator war command. wait for it to finish and try again", save_path))),
+            Err(TryLockError::Error(e)) => Err(GameError::Io(format!("failed to lock {} ({})", lock_path, e)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::fighter::{Fighter, Class};
+
+    #[test]
+    fn test_lock() {
+        let path = std::env::temp_dir().join(format!("gw_lock_test_{}.json", std::process::id())).to_str().unwrap().to_string();
+        let first = SaveLock::acquire(&path).unwrap();
+        assert!(matches!(SaveLock::acquire(&path), Err(GameError::Conflict(_)))); // exit code 6
+        drop(first);
+        let again = SaveLock::acquire(&path);
+        let _ = std::fs::remove_file(format!("{}.lock", path));
+        assert!(again.is_ok());
+    }
+
+    #[test]
+    fn test_generation_conflict() {
+        let path = std::env::temp_dir().join(format!("gw_generation_test_{}.json", std::process::id())).to_str().unwrap().to_string();
+        GameState::new_game("gen").save_to_file(&path).unwrap();
+        let mut first = GameState::load_from_file(&path).unwrap();
+        let mut second = GameState::load_from_file(&path).unwrap();
+        let f = Fighter::new(String::from("a"), String::from("o"), Class::Mutant, 3, 3, 3);
+        first.add_fighter(f.clone());
+        first.save_over(&path).unwrap();
+        second.add_fighter(f);
+        let conflict = second.save_over(&path);
+        let saved = GameState::load_from_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        for b in crate::backup::list_backups(&path) {
+            let _ = std::fs::remove_file(b);
+        }
+        assert!(matches!(conflict, Err(GameError::Conflict(_))));
+        assert_eq!((saved.generation, saved.fighters.len()), (1, 1)); // the second write didn't land
+    }
+}
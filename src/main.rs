@@ -1,23 +1,28 @@
-use argparse::{ArgumentParser, Store, Collect, IncrBy, DecrBy, StoreTrue, StoreOption};
+use argparse::{ArgumentParser, Store, Collect, IncrBy, DecrBy, StoreTrue, StoreFalse, StoreOption, StoreConst};
 use std::process::exit;
 use std::io::{stdout, stderr};
 use std::path::Path;
 use std::env::var;
-use std::fs::remove_file;
-
-use global::GwGlobalData;
-use game::GameState;
-use fighter::Fighter;
-use utils::confirm;
-
-mod game;
-mod fighter;
-mod utils;
-mod batlog;
-mod global;
-mod round;
-mod battle;
-mod boss;
+use std::fs::{remove_file, read_to_string};
+
+use gladiator_war::{global, utils, output, backup, lock, season, script, render, publish, template, csv, signup};
+use gladiator_war::render::{Style, Layout};
+use gladiator_war::template::Template;
+use gladiator_war::global::{GwGlobalData, SaveEntry};
+use gladiator_war::game::GameState;
+use gladiator_war::fighter::Fighter;
+use gladiator_war::journal::Action;
+use gladiator_war::output::Format;
+use gladiator_war::round::{Arena, Modifier, Pairing};
+use gladiator_war::report::Report;
+use gladiator_war::error::GameError;
+use cli::{confirm, GwError};
+use serde_json::{Value, json};
+use rand::{thread_rng, Rng};
+
+mod cli;
+mod shell;
+mod tui;
 
 const VERSION: &str = "0.1.0";
 
@@ -33,11 +38,14 @@ fn main() {
 }
 
 fn run() -> Result<(), i32> {
-    let mut po = utils::ProgramOptions::default(); // fucking awful
+    let mut po = cli::ProgramOptions::default(); // fucking awful
 
     let mut command = String::new();
     let mut args_2: Vec<String> = Vec::new();
     let mut global_path_option: Option<String> = None;
+    let mut logging: Option<bool> = None; // None means whatever the config says
+    let mut format: Option<String> = None;
+    let mut output: Option<Value> = None; // what --format json prints at the end
 
     {
         let mut ap = argparse::ArgumentParser::new();
@@ -48,8 +56,9 @@ fn run() -> Result<(), i32> {
         ap.refer(&mut args_2).add_argument("command", Collect, ".");
 
         ap.refer(&mut po.verbosity).add_option(&["-v"], IncrBy(1), "controls how loud the program is").add_option(&["-q"], DecrBy(1), "quiet");
-        ap.refer(&mut po.logging).add_option(&["-l"], StoreTrue, "automatically logs battles to a text file");
+        ap.refer(&mut logging).add_option(&["-l"], StoreConst(Some(true)), "automatically logs battles to a text file").add_option(&["--no-log"], StoreConst(Some(false)), "don't log battles, even if the config says to");
         ap.refer(&mut global_path_option).add_option(&["-g"], StoreOption, "path to a global data file");
+        ap.refer(&mut format).add_option(&["--format"], StoreOption, "text or json");
 
         ap.stop_on_first_argument(true);
 
@@ -59,6 +68,16 @@ fn run() -> Result<(), i32> {
         }
     }
 
+    let format = match format.map(|f| f.parse::<Format>()) {
+        Some(Ok(f)) => Some(f),
+        Some(Err(e)) => return Err(cli::error(Format::Text, GwError::Parse(e))),
+        None => None
+    };
+    if format == Some(Format::Json) {
+        po.format = Format::Json;
+        po.verbosity = -2 // nothing but json on stdout
+    }
+
     let global_path = match global_path_option {
         Some(p) => p,
         None => {
@@ -88,12 +107,17 @@ fn run() -> Result<(), i32> {
         match GwGlobalData::load_from_file(&global_path) {
             Ok(v) => v, // load + parse worked
             Err(e) => { // error
-                println!("failed to load global data: {}", e);
-                return Err(1)
+                return Err(cli::error(po.format, GwError::Io(format!("failed to load global data: {}", e))))
             }
         }
     };
 
+    po.format = format.unwrap_or(global_data.config.format);
+    po.verbosity = match po.format {
+        Format::Text => po.verbosity + global_data.config.verbosity, // -v and -q are relative to the configured level
+        Format::Json => -2
+    };
+    po.logging = logging.unwrap_or(global_data.config.auto_log);
     po.global_data = global_data;
 
     //println!("{}", po.verbosity);
@@ -103,24 +127,29 @@ fn run() -> Result<(), i32> {
     match command.as_str() { // ajal-gw-rs [options] command ...
         "load" => {
             if args_2.len() < 2 {
-                println!("not enough arguments for load command (expected >=2, found {})", args_2.len());
-                return Err(2)                
+                return Err(cli::error(po.format, GwError::Parse(format!("not enough arguments for load command (expected >=2, found {})", args_2.len()))))                
             }
 
-            let game_index = match args_2[0].parse::<usize>() { // get index of game filename in global data
+            let game_index = match po.global_data.find_save(&args_2[0]) { // name, alias or index
                 Ok(i) => i,
-                Err(_) => {
-                    println!("game number failed to parse");
-                    return Err(2)
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
                 }
             };
-            
-            if po.global_data.saves.len() <= game_index { // check game exists
-                println!("index out of range");
-                return Err(2)
-            }
 
-            let game_file = &po.global_data.saves[game_index]; // pull out path
+            let game_file = &po.global_data.saves[game_index].path.clone(); // pull out path
+            let read_only = po.global_data.saves[game_index].archived;
+
+            let _lock = match lock::SaveLock::acquire(game_file) { // held until the end of this block
+                Ok(l) => l,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+
+            if !Path::new(game_file).exists() {
+                return Err(cli::error(po.format, GwError::MissingSave(format!("save file {} is missing", game_file))))
+            }
 
             let game = match GameState::load_from_file(game_file) {
                 Ok(g) => {
@@ -130,140 +159,454 @@ fn run() -> Result<(), i32> {
                     g
                 }
                 Err(e) => {
-                    println!("{}", e);
-                    return Err(1)
+                    return Err(cli::error(po.format, e))
                 }
             };
 
-            let game = match do_things_to_existing_game(args_2, game, &po) {
+            let before = (game.generation, game.journal_len);
+            let (mut game, out) = match do_things_to_existing_game(args_2, game, &po, read_only) {
                 Ok(g) => g,
-                Err((e, c)) => {
-                    println!("error: {}", e);
-                    return Err(c)
+                Err(e) => {
+                    if po.format == Format::Text && !e.message().is_empty() {
+                        print!("error: ") // the rest comes from cli::error
+                    }
+                    return Err(cli::error(po.format, e))
                 }
             };
 
-            match game.save_to_file(game_file) {
-                Ok(_) => {}
+            match game.save_over(game_file) {
+                Ok(_) => {
+                    if (game.generation, game.journal_len) != before { // something actually got written
+                        po.global_data.saves[game_index].last_modified = utils::timestamp()
+                    }
+                    output = out
+                }
                 Err(e) => {
-                    println!("{}", e);
-                    return Err(1)
+                    return Err(cli::error(po.format, e))
                 }
             }
         }
 
         "help" => {
-            print_help(); // unfinished
+            match po.format {
+                Format::Text => print_help(), // unfinished
+                Format::Json => cli::print_json(&json!({ "version": VERSION, "usage": "see the readme" }))
+            }
             return Ok(())
         }
 
         "list-saves" => { // list save games w/ numbers? exit
-            println!("printing save games:");
-            for i in 0..po.global_data.saves.len() {
-                println!("[{}] {}", i, po.global_data.saves[i])
+            match po.format {
+                Format::Text => cli::list_saves(&po.global_data),
+                Format::Json => output = Some(output::saves(&po.global_data.saves))
             }
         }
         "add-save" => { // check validity of save, add, exit
-            if args_2.len() != 1 {
-                println!("wrong number of arguments for add-save command (expected 1)");
-                return Err(2)
+            if args_2.is_empty() || args_2.len() > 2 {
+                return Err(cli::error(po.format, GwError::Parse("wrong number of arguments for add-save command (expected 1 or 2)".into())))
             }
-            match po.global_data.add_save(&args_2[0]) {
-                Ok(_) => {},
+            match po.global_data.add_save(&args_2[0], args_2.get(1).map(|n| n.as_str())) {
+                Ok(e) => {
+                    if po.verbosity > -1 { println!("added {} as {}", e.path, e.name) }
+                    output = Some(output::save(po.global_data.saves.len() - 1, po.global_data.saves.last().unwrap()))
+                }
                 Err(e) => {
-                    println!("error adding save: {}", e);
-                    return Err(1)
+                    return Err(cli::error(po.format, e))
                 }
             }
         }
         "delete-save" => {
             if args_2.len() != 1 {
-                println!("wrong number of arguments for delete-save command (expected 1)");
-                return Err(2)
+                return Err(cli::error(po.format, GwError::Parse("wrong number of arguments for delete-save command (expected 1)".into())))
             }
-            let game_index = match args_2[0].parse::<usize>() { // get index of game filename in global data
+            let game_index = match po.global_data.find_save(&args_2[0]) {
                 Ok(i) => i,
-                Err(_) => {
-                    println!("game number failed to parse");
-                    return Err(2)
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
                 }
             };
-            if game_index >= po.global_data.saves.len() {
-                println!("index out of range");
-                return Err(2)
-            }
-            let game_path = &po.global_data.saves[game_index];
-            if confirm(&format!("are you sure you want to delete the saved game at {}? [y/n]", game_path)) {
+            let game_path = &po.global_data.saves[game_index].path.clone();
+            let _lock = match lock::SaveLock::acquire(game_path) { // not out from under a command that's using it
+                Ok(l) => l,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            let deleted = confirm(&format!("are you sure you want to delete the saved game at {}? [y/n]", game_path));
+            if deleted {
                 if po.verbosity > 0 { println!("removing file...") }
                 let _ = remove_file(game_path);
+                let _ = remove_file(format!("{}.lock", game_path)); // we hold it, so nobody else is waiting on it
                 if po.verbosity > 0 { println!("removing entry in list...") }
                 po.global_data.saves.remove(game_index);
             }
+            output = Some(json!({ "path": game_path, "deleted": deleted }))
         }
 
-        "new-game" => { // new-game name path
-            let alen = args_2.len();
-            if alen == 0 {
-                println!("not enough arguments for new-game command (expected >=1, found {})", alen);
-                return Err(2)
+        "archive-save" | "unarchive-save" => {
+            if args_2.len() != 1 {
+                return Err(cli::error(po.format, GwError::Parse(format!("wrong number of arguments for {} command (expected 1)", command))))
             }
-            let game = GameState::new_game(&args_2[0]);
-            let filename = match alen {
-                1 => {
-                    let mut fname = args_2[0].replace(' ', "_");
-                    fname.push_str(".json");
-                    match utils::get_non_repeating_filename(&fname) {
-                        Ok(n) => n,
-                        Err(e) => {
-                            println!("{}", e);
-                            return Err(1)
+            let game_index = match po.global_data.find_save(&args_2[0]) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            po.global_data.saves[game_index].archived = command == "archive-save";
+            output = Some(output::save(game_index, &po.global_data.saves[game_index]))
+        }
+
+        "config" => { // config [get key | set key value | unset key]
+            let config = &mut po.global_data.config;
+            let result = match (args_2.first().map(|a| a.as_str()), args_2.len()) {
+                (None, _) => {
+                    match po.format {
+                        Format::Text => {
+                            for k in config.keys() {
+                                println!("{} = {}", k, config.get(&k).unwrap())
+                            }
                         }
+                        Format::Json => output = Some(serde_json::to_value(&*config).unwrap())
+                    }
+                    Ok(())
+                }
+                (Some("get"), 2) => config.get(&args_2[1]).map(|v| match po.format {
+                    Format::Text => println!("{}", v),
+                    Format::Json => output = Some(json!({ "key": args_2[1], "value": serde_json::to_value(&*config).unwrap()[&args_2[1]] }))
+                }),
+                (Some("set"), 3) => config.set(&args_2[1], &args_2[2]),
+                (Some("unset"), 2) => config.unset(&args_2[1]),
+                _ => {
+                    return Err(cli::error(po.format, GwError::Parse("usage: config [get KEY | set KEY VALUE | unset KEY]".into())))
+                }
+            };
+            if let Err(e) = result {
+                return Err(cli::error(po.format, GwError::Parse(e)))
+            }
+        }
+
+        "shell" => { // shell save
+            if args_2.len() != 1 {
+                return Err(cli::error(po.format, GwError::Parse("wrong number of arguments for shell command (expected 1)".into())))
+            }
+            let game_index = match po.global_data.find_save(&args_2[0]) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            if po.format == Format::Json {
+                return Err(cli::error(po.format, GwError::Parse("shell is interactive, so it has no json output".into())))
+            }
+            if let Err(e) = shell::run_shell(&mut po, game_index, &format!("{}.history", global_path)) {
+                let _ = po.global_data.save_to_file(&global_path); // keep last_modified for whatever did get saved
+                return Err(cli::error(po.format, e))
+            }
+        }
+
+        "tui" => { // tui save
+            if args_2.len() != 1 {
+                return Err(cli::error(po.format, GwError::Parse("wrong number of arguments for tui command (expected 1)".into())))
+            }
+            let game_index = match po.global_data.find_save(&args_2[0]) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            if po.format == Format::Json {
+                return Err(cli::error(po.format, GwError::Parse("tui is interactive, so it has no json output".into())))
+            }
+            if let Err(e) = tui::run_tui(&mut po, game_index) {
+                let _ = po.global_data.save_to_file(&global_path);
+                return Err(cli::error(po.format, e))
+            }
+        }
+
+        "hall-of-fame" => { // hall-of-fame [n] [--refresh]
+            let mut top: usize = 5;
+            let mut refresh = false;
+            args_2.insert(0, String::from("hall-of-fame"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("all time records across every registered save");
+                ap.refer(&mut top).add_argument("top", Store, "how many to show in each list (default 5)");
+                ap.refer(&mut refresh).add_option(&["--refresh"], StoreTrue, "ignore the cached results");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok(()),
+                    Err(e) => return Err(e)
+                }
+            }
+            let hall = po.global_data.hall_of_fame(refresh);
+            match po.format {
+                Format::Text => cli::print_hall_of_fame(hall, top),
+                Format::Json => output = Some(output::hall_of_fame(hall))
+            }
+        }
+
+        "rename-save" | "alias-save" => { // rename-save save new_name
+            if args_2.len() != 2 {
+                return Err(cli::error(po.format, GwError::Parse(format!("wrong number of arguments for {} command (expected 2)", command))))
+            }
+            let game_index = match po.global_data.find_save(&args_2[0]) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            let entry = &po.global_data.saves[game_index];
+            let unchanged = if command == "rename-save" { entry.name == args_2[1] } else { entry.alias.as_deref() == Some(args_2[1].as_str()) };
+            if !unchanged { // giving a save the name it already has is fine, it just doesn't do anything
+                if let Err(e) = po.global_data.check_name(&args_2[1]) {
+                    return Err(cli::error(po.format, GwError::Rule(e)))
+                }
+            }
+            let entry = &mut po.global_data.saves[game_index];
+            if command == "rename-save" {
+                entry.name = args_2[1].clone()
+            }
+            else {
+                entry.alias = Some(args_2[1].clone())
+            }
+            output = Some(output::save(game_index, entry))
+        }
+
+        "restore-backup" => { // restore-backup game [backup]
+            if args_2.is_empty() || args_2.len() > 2 {
+                return Err(cli::error(po.format, GwError::Parse("wrong number of arguments for restore-backup command (expected 1 or 2)".into())))
+            }
+            let game_index = match po.global_data.find_save(&args_2[0]) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            let game_path = &po.global_data.saves[game_index].path.clone();
+            let backups = backup::list_backups(game_path);
+
+            if args_2.len() == 1 { // just list them
+                if po.format == Format::Json {
+                    output = Some(json!({ "backups": backups }))
+                }
+                else if backups.is_empty() {
+                    println!("no backups found for {}", game_path)
+                }
+                for (i, b) in backups.iter().enumerate() {
+                    if po.verbosity > -2 { println!("[{}] {}", i, b) }
+                }
+            }
+            else {
+                if po.global_data.saves[game_index].archived {
+                    return Err(cli::error(po.format, GwError::Rule(format!("{} is archived, so it can't be changed (use unarchive-save first)", po.global_data.saves[game_index].name))))
+                }
+                let backup_index = match args_2[1].parse::<usize>() {
+                    Ok(i) => i,
+                    Err(_) => {
+                        return Err(cli::error(po.format, GwError::Parse("backup number failed to parse".into())))
                     }
+                };
+                if backup_index >= backups.len() {
+                    return Err(cli::error(po.format, GwError::InvalidIndex("backup index out of range".into())))
+                }
+                let backup_path = &backups[backup_index];
+                if let Err(e) = GameState::peek_file(backup_path) { // dont restore junk
+                    let message = format!("backup is not a valid save: {}", e);
+                    return Err(cli::error(po.format, GwError::from(e).with_message(message)))
                 }
-                2..=usize::MAX => {
-                    match utils::get_non_repeating_filename(&args_2[1]) {
-                        Ok(n) => n,
+                let _lock = match lock::SaveLock::acquire(game_path) {
+                    Ok(l) => l,
+                    Err(e) => {
+                        return Err(cli::error(po.format, e))
+                    }
+                };
+                let restored = confirm(&format!("are you sure you want to replace {} with {}? [y/n]", game_path, backup_path));
+                output = Some(json!({ "path": game_path, "backup": backup_path, "restored": restored }));
+                if restored {
+                    match backup::restore_backup(game_path, backup_path) {
+                        Ok(_) => {
+                            if po.verbosity > -1 { println!("restored {}", backup_path) }
+                        }
                         Err(e) => {
-                            println!("{}", e);
-                            return Err(1)
+                            return Err(cli::error(po.format, GwError::Io(e)))
                         }
                     }
                 }
-                _ => {
-                    unreachable!()
+            }
+        }
+
+        "import" => { // import path [new path]
+            if args_2.is_empty() || args_2.len() > 2 {
+                return Err(cli::error(po.format, GwError::Parse("wrong number of arguments for import command (expected 1 or 2)".into())))
+            }
+            let game = match GameState::load_from_file(&args_2[0]) {
+                Ok(g) => g,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
                 }
             };
-            match game.save_to_file(&filename) {
+            let dest = match args_2.get(1) {
+                Some(d) => d.clone(),
+                None => { // swap formats by default
+                    let (stem, ext) = utils::get_last(&args_2[0], '.');
+                    let stem = if stem.is_empty() { ext } else { stem }; // no extension at all
+                    match ext {
+                        "db" | "sqlite" | "sqlite3" => format!("{}.json", stem),
+                        _ => format!("{}.db", stem)
+                    }
+                }
+            };
+            let dest = match utils::get_non_repeating_filename(&dest) {
+                Ok(n) => n,
+                Err(e) => {
+                    return Err(cli::error(po.format, GwError::Io(e)))
+                }
+            };
+            match game.export(&dest) {
                 Ok(_) => {
-                    let path = Path::new(&filename);
-                    let path = path.canonicalize().unwrap(); // this shouldnt fail because the file definitely exists
-                    let filename = path.to_str().unwrap(); // also shouldnt fail unless the user summons demons with their file system
-                    po.global_data.saves.push(filename.to_string())
+                    let path = Path::new(&dest).canonicalize().unwrap(); // file definitely exists now
+                    let dest = path.to_str().unwrap().to_string();
+                    let name = po.global_data.unique_name(&global::make_save_name(&game.season_name));
+                    if po.verbosity > -1 { println!("imported {} as {} ({})", args_2[0], dest, name) }
+                    let now = utils::timestamp();
+                    po.global_data.saves.push(SaveEntry {
+                        name,
+                        alias: None,
+                        season_name: game.season_name.clone(),
+                        path: dest,
+                        created: now.clone(),
+                        last_modified: now,
+                        archived: false
+                    });
+                    output = Some(output::save(po.global_data.saves.len() - 1, po.global_data.saves.last().unwrap()))
                 }
                 Err(e) => {
-                    println!("{}", e);
-                    return Err(1)
+                    return Err(cli::error(po.format, e))
                 }
             }
         }
 
+        "new-game" => { // new-game name path
+            let alen = args_2.len();
+            if alen == 0 {
+                return Err(cli::error(po.format, GwError::Parse(format!("not enough arguments for new-game command (expected >=1, found {})", alen))))
+            }
+            let game = GameState::new_game(&args_2[0]);
+            if let Err(e) = save_new_game(&mut po, &game, args_2.get(1)) {
+                return Err(cli::error(po.format, e))
+            }
+            output = Some(output::save(po.global_data.saves.len() - 1, po.global_data.saves.last().unwrap()))
+        }
+
+        "new-season" => { // new-season from_save name [path] [options]
+            let mut from = String::new();
+            let mut name = String::new();
+            let mut path: Option<String> = None;
+            let mut rule = po.global_data.config.carry_over();
+            args_2.insert(0, String::from("new-season"));
+
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("starts a new season with the survivors of an old one");
+                ap.refer(&mut from).add_argument("from", Store, "the save to carry fighters over from").required();
+                ap.refer(&mut name).add_argument("name", Store, "the new season name").required();
+                ap.refer(&mut path).add_argument("path", StoreOption, "where to put the new save");
+                ap.refer(&mut rule.keep_stats).add_option(&["--keep-stats"], Store, "percentage of stats kept (default from config)");
+                ap.refer(&mut rule.keep_rating).add_option(&["--keep-rating"], StoreTrue, "keep ratings instead of resetting them").add_option(&["--reset-rating"], StoreFalse, "reset ratings to 0");
+                ap.refer(&mut rule.kill_bonus).add_option(&["--kill-bonus"], Store, "unspent points per kill last season (default from config)");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok(()), // --help
+                    Err(e) => return Err(e)
+                }
+            }
+
+            let from_index = match po.global_data.find_save(&from) {
+                Ok(i) => i,
+                Err(e) => {
+                    return Err(cli::error(po.format, e))
+                }
+            };
+            let from_path = po.global_data.saves[from_index].path.clone();
+            let old = {
+                let _lock = match lock::SaveLock::acquire(&from_path) {
+                    Ok(l) => l,
+                    Err(e) => {
+                        return Err(cli::error(po.format, e))
+                    }
+                };
+                match GameState::load_from_file(&from_path) {
+                    Ok(g) => g,
+                    Err(e) => {
+                        return Err(cli::error(po.format, e))
+                    }
+                }
+            };
+            let game = match season::new_season(&old, &from_path, &name, &rule) {
+                Ok(g) => g,
+                Err(e) => {
+                    return Err(cli::error(po.format, GwError::Rule(e)))
+                }
+            };
+            if let Err(e) = save_new_game(&mut po, &game, path.as_ref()) {
+                return Err(cli::error(po.format, e))
+            }
+            if po.verbosity > -1 { println!("carried over {} of {} fighters from {}", game.fighters.len(), old.fighters.len(), old.season_name) }
+            po.global_data.saves[from_index].archived = true;
+            let mut out = output::save(po.global_data.saves.len() - 1, po.global_data.saves.last().unwrap());
+            out["carried_over"] = json!(game.fighters.len());
+            out["archived_save"] = json!(po.global_data.saves[from_index].name);
+            output = Some(out)
+        }
+
         _ => {
-            println!("unrecognised command {}", command);
-            return Err(1)
+            return Err(cli::error(po.format, GwError::Parse(format!("unrecognised command {}", command))))
         }
     }
 
     match po.global_data.save_to_file(&global_path) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            println!("{}", e);
-            Err(1)
+        Ok(_) => {
+            if po.format == Format::Json { // commands with nothing to say still say something, so bots can tell it worked
+                cli::print_json(&output.unwrap_or(json!({ "ok": true })))
+            }
+            Ok(())
         }
+        Err(e) => Err(cli::error(po.format, GwError::Io(e)))
+    }
+}
+
+fn save_new_game(po: &mut cli::ProgramOptions, game: &GameState, path: Option<&String>) -> Result<(), GwError> { // writes a brand new save and registers it
+    let filename = match path {
+        Some(p) => utils::get_non_repeating_filename(p),
+        None => utils::get_non_repeating_filename(&format!("{}.json", game.season_name.replace(' ', "_")))
+    };
+    let filename = filename.map_err(GwError::MissingSave)?; // the folder isn't there
+    let absolute = std::env::current_dir().map(|d| d.join(&filename)).unwrap_or_else(|_| filename.clone().into());
+    if let Some(e) = po.global_data.saves.iter().find(|e| Path::new(&e.path) == absolute) { // the file name was free, so this is an entry whose file got deleted
+        return Err(GwError::Rule(format!("{} is still registered as {} (use delete-save on it first)", filename, e.name)))
     }
+    game.save_to_file(&filename)?;
+    let path = Path::new(&filename);
+    let path = path.canonicalize().unwrap(); // this shouldnt fail because the file definitely exists
+    let filename = path.to_str().unwrap(); // also shouldnt fail unless the user summons demons with their file system
+    let name = po.global_data.unique_name(&global::make_save_name(&game.season_name));
+    if po.verbosity > -1 { println!("created {} as {}", filename, name) }
+    let now = utils::timestamp();
+    po.global_data.saves.push(SaveEntry {
+        name,
+        alias: None,
+        season_name: game.season_name.clone(),
+        path: filename.to_string(),
+        created: now.clone(),
+        last_modified: now,
+        archived: false
+    });
+    Ok(())
 }
 
 #[allow(unused_variables)]
-fn do_things_to_existing_game(args: Vec<String>, mut game: GameState, po: &utils::ProgramOptions) -> Result<GameState, (String, i32)> {
+fn do_things_to_existing_game(args: Vec<String>, mut game: GameState, po: &cli::ProgramOptions, read_only: bool) -> Result<(GameState, Option<Value>), GwError> { // also hands back what --format json should print
     let mut command = String::new();
     let mut args_2: Vec<String> = Vec::new();
 
@@ -279,134 +622,566 @@ fn do_things_to_existing_game(args: Vec<String>, mut game: GameState, po: &utils
 
         match ap.parse(args, &mut stdout(), &mut stderr()) {
             Ok(_) => {},
-            Err(e) => return Err((format!(""), e))
+            Err(0) => return Ok((game, None)), // --help
+            Err(_) => return Err(GwError::Parse(String::new())) // argparse already printed why
         }
     }
 
     //println!("inner: {}, {:?}", command, args_2);
 
-    match command.as_str() {
+    let json = po.format == Format::Json;
+    let mut out: Option<Value> = None;
+
+    let action = match command.as_str() { // mutating commands become actions, everything else just happens here
         //bookkeeping
+        "info" if json => {
+            out = Some(output::info(&game));
+            None
+        }
         "info" => {
             println!("{}", game.season_name);
-            println!("number of rounds: {}", game.num_rounds)
+            println!("number of rounds: {}", game.num_rounds);
+            if game.journal_mode {
+                println!("journal mode on ({} entries)", game.journal_len)
+            }
+            None
         }
         "add-fighter" => { // take args, parse into vec, parse into numbers, add as fighter, exit
             match Fighter::from_vec(&args_2) {
-                Ok(f) => game.add_fighter(f),
-                Err(e) => return Err((e, 1))
+                Ok(fighter) => Some(Action::AddFighter { fighter }),
+                Err(e) => return Err(GwError::Parse(e))
             }
         }
-        "list-fighters" => {
-            game.list_fighters();
+        "import-fighters" => { // import-fighters FILE [--map FIELD=HEADING...]
+            let mut file = String::new();
+            let mut map: Vec<String> = Vec::new();
+            args_2.insert(0, String::from("import-fighters"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("adds every fighter from a csv or json signup sheet, or none of them if anything's wrong");
+                ap.refer(&mut file).add_argument("file", Store, "the signup sheet").required();
+                ap.refer(&mut map).add_option(&["--map"], Collect, "FIELD=HEADING, for a column that can't be guessed");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
+            }
+            let map = signup::parse_map(&map).map_err(GwError::Parse)?;
+            let text = match read_to_string(&file) {
+                Ok(t) => t,
+                Err(e) => return Err(GwError::Io(format!("could not read {}: {}", file, e)))
+            };
+            let is_json = file.to_lowercase().ends_with(".json"); // only the extension, a csv cell can start with [ too
+            let taken: Vec<String> = game.fighters.iter().map(|f| f.name.clone()).collect();
+            match signup::read_signups(&text, is_json, &map, &taken) {
+                Ok(fighters) => Some(Action::AddFighters { fighters }),
+                Err(problems) => return Err(GwError::Parse(format!("{} problem{} in {}, so no fighters were added:\n{}",
+                    problems.len(), if problems.len() == 1 { "" } else { "s" }, file, problems.join("\n"))))
+            }
         }
-        "next-round" => {
-            game.display_next_round()
+        "list-fighters" => { // list-fighters [--style S | --template FILE]
+            let mut style: Option<String> = None;
+            let mut template: Option<String> = None;
+            args_2.insert(0, String::from("list-fighters"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("lists every fighter in the save");
+                ap.refer(&mut style).add_option(&["--style"], StoreOption, "text, discord or bbcode");
+                ap.refer(&mut template).add_option(&["--template"], StoreOption, "a standings template file");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
+            }
+            if json && (style.is_some() || template.is_some()) { // the json is the same whatever the look, so don't pretend otherwise
+                return Err(GwError::Parse(String::from("--style and --template only change the text table, so they can't be used with --format json")))
+            }
+            let layout = Layout::pick(style.as_deref(), template.as_deref(), &po.global_data.config.standings_template, Style::Text)?;
+            if json { out = Some(output::fighters(&game)) } else { println!("{}", layout.standings(&game)) }
+            None
+        }
+        "show-fighter" => { // show-fighter INDEX [--template FILE]
+            let mut fi: usize = 0;
+            let mut template: Option<String> = None;
+            args_2.insert(0, String::from("show-fighter"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("shows one fighter's card");
+                ap.refer(&mut fi).add_argument("index", Store, "the fighter's index").required();
+                ap.refer(&mut template).add_option(&["--template"], StoreOption, "a fighter card template file");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
+            }
+            let path = template.unwrap_or_else(|| po.global_data.config.fighter_template.clone());
+            let template = if path.is_empty() { None } else { Some(Template::load(&path)?) }; // built in card if neither is set
+            let card = match template::fighter_card(&game, fi, template.as_ref()) {
+                Some(c) => c,
+                None => return Err(GameError::FighterOutOfRange(fi).into())
+            };
+            if json {
+                out = Some(json!({ "fighter": output::fighter(fi, &game.fighters[fi]), "card": card }))
+            } else {
+                println!("{}", card)
+            }
+            None
         }
-        "show-round" => {
+        "lineage" => { // lineage index
             if args_2.len() != 1 {
-                return Err((String::from("round number required"), 2))
+                return Err(GwError::Parse(String::from("expected a fighter index")))
             }
-            let ri = match args_2[0].parse::<usize>() {
-                Ok(v) => v,
-                Err(_) => return Err((String::from("round number failed to parse"), 2))
+            let index = match args_2[0].parse::<usize>() {
+                Ok(i) => i,
+                Err(_) => return Err(GwError::Parse(String::from("fighter index failed to parse")))
             };
-            game.display_round(ri)
+            let l = season::lineage(&game, index)?;
+            if json {
+                out = Some(output::lineage(&l))
+            } else {
+                cli::print_lineage(&l)
+            }
+            None
         }
-        "log-round" => {
-            if args_2.len() == 0 {
-                return Err((String::from("round number required"), 2))
+        "next-round" => {
+            match (json, game.next_round_report()?) {
+                (true, r) => out = Some(json!({ "round": r.as_ref().map(output::round) })),
+                (false, Some(r)) => println!("{}", r),
+                (false, None) => println!("no round scheduled")
             }
-            let mut path = None;
-            if args_2.len() > 1 {
-                path = Some(args_2[1].as_str())
+            None
+        }
+        "show-round" => { // show-round INDEX [--style S | --template FILE]
+            let mut ri: usize = 0;
+            let mut style: Option<String> = None;
+            let mut template: Option<String> = None;
+            args_2.insert(0, String::from("show-round"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("shows a round from the past");
+                ap.refer(&mut ri).add_argument("index", Store, "the round number").required();
+                ap.refer(&mut style).add_option(&["--style"], StoreOption, "text, discord or bbcode");
+                ap.refer(&mut template).add_option(&["--template"], StoreOption, "a round template file");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
             }
-            //dbg!(path);
-            let ri = match args_2[0].parse::<usize>() { // round index
-                Ok(v) => v,
-                Err(_) => return Err((String::from("round number failed to parse"), 2))
-            };
-            game.log_round(ri, path, po)
+            let layout = Layout::pick(style.as_deref(), template.as_deref(), &po.global_data.config.round_template, Style::Text)?;
+            let r = game.round_report(ri)?;
+            let chunks = layout.round(&r, &game.season_name);
+            if json {
+                out = Some(json!({ "round": output::round(&r), "chunks": chunks }))
+            } else {
+                println!("{}", render::join_chunks(&chunks))
+            }
+            None
+        }
+        "log-round" => { // log-round INDEX [PATH] [--style S | --template FILE]
+            let mut ri: usize = 0;
+            let mut path: Option<String> = None;
+            let mut style: Option<String> = None;
+            let mut template: Option<String> = None;
+            args_2.insert(0, String::from("log-round"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("writes a round to a file");
+                ap.refer(&mut ri).add_argument("index", Store, "the round number").required();
+                ap.refer(&mut path).add_argument("path", StoreOption, "where to write it. log_name from the config if left out");
+                ap.refer(&mut style).add_option(&["--style"], StoreOption, "text, discord or bbcode. log_style from the config if left out");
+                ap.refer(&mut template).add_option(&["--template"], StoreOption, "a round template file");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
+            }
+            let config = &po.global_data.config;
+            let layout = Layout::pick(style.as_deref(), template.as_deref(), &config.round_template, config.log_style)?;
+            let f = game.log_round(ri, path.as_deref(), &layout, config)?;
+            if po.verbosity > 0 { println!("logged round {} to {}", ri, f) }
+            out = Some(json!({ "path": f }));
+            None
         }
         "add-stats" => { // add-stats fi st sp sk
             let a2l = args_2.len();
             if a2l != 4 {
-                return Err((format!("expected 1 index and 3 stats, found {} arguments", a2l), 2))
+                return Err(GwError::Parse(format!("expected 1 index and 3 stats, found {} arguments", a2l)))
             }
 
             let fi = match args_2[0].parse::<usize>() { // fighter index
                 Ok(v) => v,
                 Err(_) => {
-                    return Err((format!("{} does not parse to usize", args_2[0]), 2))
+                    return Err(GwError::Parse(format!("{} does not parse to usize", args_2[0])))
                 }
             };
 
-            args_2.remove(0);
-
-            let f = &mut game.fighters[fi];
-
-            let mut stats_v: Vec<i32> = Vec::new();
-            for stat in args_2 {
+            let mut stats = [0; 3];
+            for (i, stat) in args_2[1..].iter().enumerate() {
                 match stat.parse::<i32>() {
-                    Ok(v) => stats_v.push(v),
+                    Ok(v) => stats[i] = v,
                     Err(_) => {
-                        return Err((format!("{} does not parse to int!", stat), 2))
+                        return Err(GwError::Parse(format!("{} does not parse to int!", stat)))
                     }
                 }
             }
 
-            f.strength += stats_v[0];
-            f.speed += stats_v[1];
-            f.skill += stats_v[2];
-            f.unspent_points -= stats_v.iter().sum::<i32>();
+            Some(Action::AddStats { fighter: fi, stats })
+        }
+        "edit-fighter" => { // edit-fighter fi --field value ...
+            if args_2.is_empty() {
+                return Err(GwError::Parse(String::from("fighter index required")))
+            }
+            let fi = match args_2[0].parse::<usize>() { // fighter index
+                Ok(v) => v,
+                Err(_) => {
+                    return Err(GwError::Parse(format!("{} does not parse to usize", args_2[0])))
+                }
+            };
+
+            let mut values: Vec<Option<String>> = vec![None; Fighter::EDITABLE_FIELDS.len()];
+            let mut note: Option<String> = None;
+            let flags: Vec<String> = Fighter::EDITABLE_FIELDS.iter().map(|f| format!("--{}", f)).collect();
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("edits any field of a fighter");
+                for (flag, v) in flags.iter().zip(values.iter_mut()) {
+                    ap.refer(v).add_option(&[flag.as_str()], StoreOption, "new value for this field");
+                }
+                ap.refer(&mut note).add_option(&["--note"], StoreOption, "reason for the edit, kept in the audit trail");
+                let mut ap_args = args_2.clone();
+                ap_args[0] = String::from("edit-fighter"); // argparse wants a program name in args[0]
+                match ap.parse(ap_args, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
+            }
 
-            println!("adding stats {}, {}, {} to fighter {}", stats_v[0], stats_v[1], stats_v[2], f.name)
+            let changes: Vec<(String, String)> = Fighter::EDITABLE_FIELDS.iter().zip(values)
+                .filter_map(|(field, v)| v.map(|v| (field.to_string(), v)))
+                .collect();
+            Some(Action::EditFighter { fighter: fi, changes, note })
+        }
+        "list-edits" => {
+            if json { out = Some(output::edits(&game.edits)) } else { cli::list_edits(&game) }
+            None
         }
         "arrange-match" => { // arrange-match f1i f2i
             if args_2.len() != 2 {
-                return Err((String::from("2 args required"), 2))
+                return Err(GwError::Parse(String::from("2 args required")))
             }
             let f1i = match args_2[0].parse::<usize>() { // fighter index
                 Ok(v) => v,
                 Err(_) => {
-                    return Err((format!("{} does not parse to usize", args_2[0]), 2))
+                    return Err(GwError::Parse(format!("{} does not parse to usize", args_2[0])))
                 }
             };
             let f2i = match args_2[1].parse::<usize>() { // fighter index
                 Ok(v) => v,
                 Err(_) => {
-                    return Err((format!("{} does not parse to usize", args_2[1]), 2))
+                    return Err(GwError::Parse(format!("{} does not parse to usize", args_2[1])))
                 }
             };
-            match game.arrange_match(f1i, f2i) {
-                Ok(_) => {}
-                Err(e) => return Err((e, 1))
-            }
+            Some(Action::ArrangeMatch { fighter_1: f1i, fighter_2: f2i })
         }
 
         // running
-        "run-round" => { // run match, store results in log, possibly give hr text log file, exit
-            game.run_round(po)
+        "run-round" => { // run-round [--dry-run] [--seed S]
+            let mut dry_run = false;
+            let mut seed: Option<u64> = None;
+            args_2.insert(0, String::from("run-round"));
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("runs the next round");
+                ap.refer(&mut dry_run).add_option(&["--dry-run"], StoreTrue, "show what would happen, without changing anything");
+                ap.refer(&mut seed).add_option(&["--seed"], StoreOption, "seed for the dice, so a dry run can be done for real");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)),
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
+            }
+            let seed = seed.unwrap_or_else(|| thread_rng().gen());
+            if dry_run { // works on archived saves too, since nothing changes
+                let r = game.preview_round(seed)?;
+                if json {
+                    out = Some(json!({ "dry_run": true, "seed": seed, "round": output::round(&r) }))
+                } else if po.verbosity > -1 {
+                    println!("{}", r);
+                    println!("dry run, nothing was saved. use `run-round --seed {}` to run it for real", seed)
+                }
+                None
+            } else {
+                Some(Action::RunRound { seed, round: None })
+            }
         }
         "new-round" => { // new argparser for arena and mod??
-            match game.new_round(po, &mut args_2) {
-                Ok(()) => {},
-                Err(e) => return Err((e, 2))
+            let mut arena: Option<String> = None;
+            let mut modifier: Option<String> = None;
+            let mut pairing: Option<String> = None;
+            args_2.insert(0, String::from("new-round")); // argparse needs the name of the program/command as args[0] to work
+
+            {
+                let mut ap = ArgumentParser::new();
+                ap.set_description("generates a new round in the current loaded game");
+                ap.refer(&mut arena).add_option(&["-a"], StoreOption, "choose an arena manually");
+                ap.refer(&mut modifier).add_option(&["-m"], StoreOption, "chose a modifier manually");
+                ap.refer(&mut pairing).add_option(&["-p"], StoreOption, "random or rating. pairing from the config if left out");
+                match ap.parse(args_2, &mut stdout(), &mut stderr()) {
+                    Ok(_) => {},
+                    Err(0) => return Ok((game, None)), // just wanted the help
+                    Err(_) => return Err(GwError::Parse(String::new()))
+                }
             }
+            let arena = match arena {
+                Some(a) => {
+                    match a.parse::<Arena>() {
+                        Ok(v) => Some(v),
+                        Err(e) => return Err(GwError::Parse(e))
+                    }
+                }
+                None => None
+            };
+            let modifier = match modifier {
+                Some(m) => {
+                    match m.parse::<Modifier>() {
+                        Ok(v) => Some(v),
+                        Err(e) => return Err(GwError::Parse(e))
+                    }
+                }
+                None => None
+            };
+            let pairing = match pairing {
+                Some(p) => p.parse::<Pairing>().map_err(GwError::Parse)?,
+                None => po.global_data.config.pairing
+            };
+            Some(Action::NewRound { arena, modifier, pairing, seed: thread_rng().gen(), round: None })
         }
         "cancel-round" => {
-            game.cancel_next_round(po)
+            Some(Action::CancelRound)
+        }
+
+        // history
+        "undo" => { // undo [n]
+            let n = match args_2.first() {
+                Some(a) => match a.parse::<usize>() {
+                    Ok(v) => v,
+                    Err(_) => return Err(GwError::Parse(format!("{} does not parse to usize", a)))
+                }
+                None => 1
+            };
+            Some(Action::Undo { n })
+        }
+        "history" => {
+            if json { out = Some(output::history(&game)) } else { cli::list_history(&game) }
+            None
+        }
+        "check" => { // check [--repair]
+            match args_2.first().map(|a| a.as_str()) {
+                None => {
+                    if json { out = Some(output::problems(&game.check())) } else { cli::print_problems(&game.check()) }
+                    None
+                }
+                Some("--repair") => Some(Action::Repair),
+                Some(a) => return Err(GwError::Parse(format!("unrecognised argument {} (expected --repair)", a)))
+            }
+        }
+        "export" if args_2.first().map(|a| a.as_str()) == Some("csv") => { // export csv dir
+            if args_2.len() != 2 {
+                return Err(GwError::Parse(String::from("folder to export to required (export csv DIR)")))
+            }
+            let files = csv::export_csv(&game, &args_2[1])?;
+            if po.verbosity > -1 { println!("exported fighters.csv, rounds.csv and battles.csv to {}", args_2[1]) }
+            out = Some(json!({ "dir": args_2[1], "files": files }));
+            None
+        }
+        "export" => { // export path
+            if args_2.len() != 1 {
+                return Err(GwError::Parse(String::from("export path required")))
+            }
+            let path = match utils::get_non_repeating_filename(&args_2[0]) {
+                Ok(p) => p,
+                Err(e) => return Err(GwError::Io(e))
+            };
+            match game.export(&path) {
+                Ok(_) => {
+                    if po.verbosity > -1 { println!("exported to {}", path) }
+                    out = Some(json!({ "path": path }));
+                    None
+                }
+                Err(e) => return Err(e.into())
+            }
+        }
+        "journal" => { // journal on|off|show|verify
+            if args_2.len() != 1 {
+                return Err(GwError::Parse(String::from("expected one of on, off, show, verify")))
+            }
+            let result = match args_2[0].as_str() {
+                "on" | "off" if read_only => Err(format!("{} is archived, so it can't be changed (use unarchive-save first)", game.season_name)),
+                "on" => game.start_journal(),
+                "off" => game.stop_journal(),
+                "show" => {
+                    if json { out = Some(output::journal(&game)) } else { cli::show_journal(&game) }
+                    Ok(())
+                }
+                "verify" => {
+                    let r = game.verify_journal().map_err(String::from);
+                    if r.is_ok() && po.verbosity > -1 {
+                        println!("journal ok ({} entries)", game.journal_len)
+                    }
+                    out = Some(json!({ "ok": r.is_ok(), "entries": game.journal_len }));
+                    r
+                }
+                a => return Err(GwError::Parse(format!("unrecognised journal command {}", a)))
+            };
+            match result {
+                Ok(_) => None,
+                Err(e) => return Err(GwError::Rule(e))
+            }
+        }
+
+        "publish" => { // publish DIR
+            if args_2.len() != 1 {
+                return Err(GwError::Parse(String::from("folder to publish to required")))
+            }
+            let files = publish::publish(&game, &args_2[0])?;
+            if po.verbosity > -1 {
+                println!("published {} pages to {}", files.len(), args_2[0])
+            }
+            if po.verbosity > 0 {
+                for f in &files {
+                    println!("\t{}", f)
+                }
+            }
+            out = Some(json!({ "dir": args_2[0], "files": files }));
+            None
+        }
+        "run-script" => { // run-script FILE
+            if args_2.len() != 1 {
+                return Err(GwError::Parse(String::from("script path required")))
+            }
+            let text = match read_to_string(&args_2[0]) {
+                Ok(t) => t,
+                Err(e) => return Err(GwError::Io(format!("could not read {}: {}", args_2[0], e)))
+            };
+            let mut results = Vec::new();
+            let lines = script::parse_script(&text)?;
+            let batch = game.start_batch();
+            for line in lines {
+                if po.verbosity > 0 {
+                    println!("> {}", line.args.join(" "))
+                }
+                let mut args = line.args.clone();
+                args.insert(0, String::from("run-script")); // argparse wants something in args[0]
+                // an error hands back no game, so whatever the earlier lines did never reaches the save
+                let (g, o) = do_things_to_existing_game(args, game, po, read_only).map_err(|e| { let m = line.fail(e.message()); e.with_message(m) })?;
+                game = g;
+                results.push(json!({ "line": line.line_no, "command": line.args.join(" "), "output": o }));
+            }
+            game.finish_batch(batch, &format!("run-script {}", args_2[0])); // one undo step for the lot
+            out = Some(json!({ "lines": results }));
+            None
         }
 
         _ => {
-            return Err((format!("unrecognised command {}", command), 1))
+            return Err(GwError::Parse(format!("unrecognised command {}", command)))
+        }
+    };
+
+    if let Some(a) = action {
+        if read_only {
+            return Err(GwError::Rule(format!("{} is archived, so it can't be changed (use unarchive-save first)", game.season_name)))
+        }
+        let command = a.to_string();
+        let outcome = cli::apply(&mut game, a, po)?;
+        cli::print_report(&outcome.report, po);
+        let mut o = json!({ "command": command, "changed": outcome.changed });
+        match &outcome.report {
+            Report::RoundScheduled(r) | Report::RoundRun(r) => o["round"] = output::round(r),
+            Report::Edited(edits) => o["edits"] = json!(edits),
+            Report::FightersAdded(fighters) => o["fighters"] = json!(fighters),
+            _ => {}
         }
+        out = Some(o);
     }
 
-    Ok(game)
+    Ok((game, out))
 }
 
 fn print_help() { // FIX THIS
     println!("welcome to version {} of the gladiator war CLI", VERSION);
     println!("please see the readme for usage instructions. it's kinda complicated")
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gladiator_war::fighter::Class;
+
+    fn run_script(game: GameState, name: &str, script: &str) -> Result<GameState, GwError> { // the way load runs it, with nothing saved on an error
+        let path = std::env::temp_dir().join(format!("gw_{}_{}.txt", name, std::process::id())).to_string_lossy().to_string();
+        std::fs::write(&path, script).unwrap();
+        let po = cli::ProgramOptions { verbosity: -2, ..cli::ProgramOptions::default() };
+        let result = do_things_to_existing_game(vec![String::from("x"), String::from("run-script"), path.clone()], game, &po, false);
+        let _ = remove_file(&path);
+        result.map(|(g, _)| g)
+    }
+
+    fn journalled_game(path: &str) -> GameState {
+        let mut game = GameState::new_game("script");
+        for n in ["a", "b", "c", "d"] {
+            game.add_fighter(Fighter::new(n.to_string(), String::from("o"), Class::Mutant, 4, 4, 3))
+        }
+        game.save_to_file(path).unwrap();
+        game.start_journal().unwrap();
+        game.save_over(path).unwrap();
+        game
+    }
+
+    fn cleanup(path: &str) {
+        let _ = remove_file(path);
+        let _ = remove_file(gladiator_war::journal::journal_path(path));
+        for b in backup::list_backups(path) {
+            let _ = remove_file(b);
+        }
+    }
+
+    #[test]
+    fn test_script_rollback() {
+        let path = std::env::temp_dir().join(format!("gw_script_rollback_{}.json", std::process::id())).to_string_lossy().to_string();
+        let game = journalled_game(&path);
+        let e = run_script(game, "rollback", "add-stats 0 1 0 0\narrange-match 1 2\nadd-stats 9 1 0 0\n").unwrap_err();
+        assert_eq!(e.code(), 5); // same kind as the line's own error
+        assert!(e.message().starts_with("line 3 (add-stats 9 1 0 0): "));
+
+        let saved = GameState::load_from_file(&path).unwrap();
+        cleanup(&path);
+        assert_eq!((saved.fighters[0].strength, saved.fighters[1].pre_matched, saved.journal_len), (4, false, 1)); // the first two lines never landed
+    }
+
+    #[test]
+    fn test_script_is_one_undo_step() {
+        let path = std::env::temp_dir().join(format!("gw_script_undo_{}.json", std::process::id())).to_string_lossy().to_string();
+        let mut game = journalled_game(&path);
+        let mut before = Action::AddStats { fighter: 3, stats: [0, 0, 1] };
+        game.apply(&mut before).unwrap();
+        game.record(before);
+        let mut game = run_script(game, "undo", "add-stats 0 1 0 0\nedit-fighter 1 --owner zed\nnew-round # and a comment\n").unwrap();
+        assert_eq!(game.history().len(), 2);
+        assert!(game.history()[1].command.starts_with("run-script "));
+        game.save_over(&path).unwrap();
+
+        let mut loaded = GameState::load_from_file(&path).unwrap(); // journal replay gives the same single step
+        let verified = loaded.verify_journal();
+        assert_eq!(loaded.history().len(), 2);
+        loaded.apply(&mut Action::Undo { n: 1 }).unwrap();
+        cleanup(&path);
+        assert!(verified.is_ok(), "{:?}", verified);
+        assert_eq!((loaded.fighters[0].strength, loaded.fighters[1].owner.as_str(), loaded.next_round.is_none()), (4, "o", true));
+        assert_eq!(loaded.fighters[3].skill, 4); // the command before the script is still there
+    }
+}
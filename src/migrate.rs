@@ -0,0 +1,225 @@
+use serde_json::{Value, json};
+use std::fs;
+
+use crate::storage::storage_for;
+use crate::global::make_save_name;
+
+// bump these and add a function to the matching list whenever the save format changes
+// new config settings don't need one, missing keys just get their default
+// migration i takes a save from version i to version i + 1
+pub const GAME_SCHEMA_VERSION: u32 = 4;
+pub const GLOBAL_SCHEMA_VERSION: u32 = 5;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+const GAME_MIGRATIONS: [Migration; GAME_SCHEMA_VERSION as usize] = [game_v0_to_v1, game_v1_to_v2, game_v2_to_v3, game_v3_to_v4];
+const GLOBAL_MIGRATIONS: [Migration; GLOBAL_SCHEMA_VERSION as usize] = [global_v0_to_v1, global_v1_to_v2, global_v2_to_v3, global_v3_to_v4, global_v4_to_v5];
+
+pub fn schema_version(v: &Value) -> Result<u32, String> {
+    match v.get("schema_version") {
+        None => Ok(0), // saves from before versioning
+        Some(n) => match n.as_u64() {
+            Some(n) => Ok(n as u32),
+            None => Err(format!("schema_version {} is not a number", n))
+        }
+    }
+}
+
+fn run_migrations(v: &mut Value, migrations: &[Migration], current: u32, what: &str) -> Result<u32, String> { // returns the version it started at
+    if !v.is_object() {
+        return Err(format!("{} is not a json object", what))
+    }
+    let from = schema_version(v)?;
+    if from > current {
+        return Err(format!("{} has schema version {}, but this program only understands up to {}. time to update", what, from, current))
+    }
+    for m in &migrations[from as usize..current as usize] {
+        m(v)?
+    }
+    v["schema_version"] = json!(current);
+    Ok(from)
+}
+
+pub fn migrate_game(v: &mut Value) -> Result<u32, String> {
+    if let Some(Value::Array(history)) = v.get_mut("history") { // undo history holds whole old saves, so they need upgrading too
+        for entry in history {
+            if let Some(state) = entry.get_mut("state") {
+                migrate_game(state)?;
+            }
+        }
+    }
+    run_migrations(v, &GAME_MIGRATIONS, GAME_SCHEMA_VERSION, "save game")
+}
+
+pub fn migrate_global(v: &mut Value) -> Result<u32, String> {
+    run_migrations(v, &GLOBAL_MIGRATIONS, GLOBAL_SCHEMA_VERSION, "global data file")
+}
+
+fn game_v0_to_v1(v: &mut Value) -> Result<(), String> { // rounds get an explicit "run" flag instead of checking for empty logs
+    fn add_run_flag(round: &mut Value) {
+        if let Some(r) = round.get_mut("Standard") {
+            let run = match r.pointer("/log/fights") {
+                Some(Value::Array(f)) => !f.is_empty(),
+                _ => false
+            };
+            r["run"] = json!(run);
+        }
+    }
+
+    if let Some(Value::Array(rounds)) = v.get_mut("prev_rounds") {
+        for r in rounds {
+            add_run_flag(r)
+        }
+    }
+    if let Some(r) = v.get_mut("next_round") {
+        add_run_flag(r)
+    }
+    for field in ["edits", "history"] {
+        if v.get(field).is_none() {
+            v[field] = json!([])
+        }
+    }
+    Ok(())
+}
+
+fn game_v1_to_v2(v: &mut Value) -> Result<(), String> { // generation counter for catching concurrent edits
+    v["generation"] = json!(0);
+    Ok(())
+}
+
+fn game_v2_to_v3(v: &mut Value) -> Result<(), String> { // optional journal mode, off for existing saves
+    if v.get("journal_mode").is_none() {
+        v["journal_mode"] = json!(false);
+        v["journal_len"] = json!(0);
+    }
+    Ok(())
+}
+
+fn global_v0_to_v1(_v: &mut Value) -> Result<(), String> { // nothing changed except getting a version number
+    Ok(())
+}
+
+fn game_v3_to_v4(v: &mut Value) -> Result<(), String> { // fighters can be carried over from an earlier season
+    if let Some(Value::Array(fighters)) = v.get_mut("fighters") {
+        for f in fighters {
+            if f.get("previous").is_none() {
+                f["previous"] = Value::Null
+            }
+        }
+    }
+    Ok(())
+}
+
+fn global_v1_to_v2(v: &mut Value) -> Result<(), String> { // save list goes from bare paths to named entries
+    let paths: Vec<String> = match v.get("saves") {
+        Some(Value::Array(a)) => a.iter().filter_map(|p| p.as_str().map(String::from)).collect(),
+        _ => Vec::new()
+    };
+    let mut entries: Vec<Value> = Vec::new();
+    for path in paths {
+        let season_name = storage_for(&path).read(&path).ok() // missing saves still get an entry, so list-saves can flag them
+            .and_then(|g| g.get("season_name").and_then(|n| n.as_str()).map(String::from))
+            .unwrap_or_default();
+        let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(t) => chrono::DateTime::<chrono::Local>::from(t).format("%Y-%m-%d %H:%M:%S").to_string(),
+            Err(_) => String::from("unknown")
+        };
+        let base = if season_name.is_empty() { make_save_name(&path) } else { make_save_name(&season_name) };
+        let mut name = base.clone();
+        let mut i = 2;
+        while entries.iter().any(|e| e["name"] == json!(name)) {
+            name = format!("{}-{}", base, i);
+            i += 1
+        }
+        entries.push(json!({
+            "name": name,
+            "alias": null,
+            "season_name": season_name,
+            "path": path,
+            "created": modified, // best guess
+            "last_modified": modified
+        }));
+    }
+    v["saves"] = Value::Array(entries);
+    Ok(())
+}
+
+fn global_v2_to_v3(v: &mut Value) -> Result<(), String> { // finished seasons can be archived
+    if let Some(Value::Array(saves)) = v.get_mut("saves") {
+        for s in saves {
+            s["archived"] = json!(false)
+        }
+    }
+    Ok(())
+}
+
+fn global_v3_to_v4(v: &mut Value) -> Result<(), String> { // somewhere to keep the hall of fame between runs
+    v["hall_of_fame"] = Value::Null;
+    Ok(())
+}
+
+fn global_v4_to_v5(v: &mut Value) -> Result<(), String> { // settings move into their own section
+    let mut config = json!({ // the defaults as they were in version 5, so this doesn't change when they do
+        "verbosity": 0,
+        "auto_log": false,
+        "log_dir": "",
+        "log_name": "%S_batlog_%R.txt",
+        "keep_stats": 100,
+        "keep_rating": false,
+        "kill_bonus": 0
+    });
+    if let Some(obj) = v.as_object_mut() {
+        if let Some(name) = obj.remove("default_batlog_name") {
+            config["log_name"] = name
+        }
+    }
+    v["config"] = config;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_game_v0() {
+        let mut v = json!({
+            "prev_rounds": [{"Standard": {"log": {"fights": [{}]}}}],
+            "next_round": {"Standard": {"log": {"fights": []}}}
+        });
+        assert_eq!(migrate_game(&mut v), Ok(0));
+        assert_eq!(v["schema_version"], json!(GAME_SCHEMA_VERSION));
+        assert_eq!(v["prev_rounds"][0]["Standard"]["run"], json!(true));
+        assert_eq!(v["next_round"]["Standard"]["run"], json!(false));
+        assert_eq!(v["edits"], json!([]));
+    }
+    #[test]
+    fn test_global_v4() {
+        let mut v = json!({"schema_version": 4, "saves": [], "hall_of_fame": null, "default_batlog_name": "%S_%R.log"});
+        assert_eq!(migrate_global(&mut v), Ok(4));
+        assert_eq!(v["config"]["log_name"], json!("%S_%R.log"));
+        let config: crate::config::Config = serde_json::from_value(v["config"].clone()).unwrap(); // newer settings are left to their defaults
+        assert_eq!(config.log_name, "%S_%R.log");
+        assert_eq!(config.keep_stats, 100);
+    }
+    #[test]
+    fn test_too_new() {
+        let mut v = json!({"schema_version": GAME_SCHEMA_VERSION + 1});
+        assert!(migrate_game(&mut v).is_err());
+    }
+    #[test]
+    fn test_backup_before_migrating() {
+        let path = std::env::temp_dir().join(format!("gw_migrate_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let raw = r#"{"prev_rounds": "junk", "next_round": null}"#;
+        std::fs::write(path, raw).unwrap();
+        // the upgrade fails, but the untouched original is still copied aside first
+        assert!(crate::game::GameState::load_from_file(path).is_err());
+        let backup = format!("{}.v0.bak", path);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), raw);
+        // peeking never writes a backup
+        std::fs::remove_file(&backup).unwrap();
+        assert!(crate::game::GameState::peek_file(path).is_err());
+        assert!(!std::path::Path::new(&backup).exists());
+        std::fs::remove_file(path).unwrap();
+    }
+}
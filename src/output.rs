@@ -0,0 +1,195 @@
+use serde::{Serialize, Deserialize};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::game::GameState;
+use crate::fighter::Fighter;
+use crate::report::RoundReport;
+use crate::battle::BattleResult;
+use crate::global::SaveEntry;
+use crate::check::{Problem, Fix};
+use crate::hall::HallOfFame;
+use crate::edit::FighterEdit;
+
+// everything --format json prints is built here, so the structures stay the same from command to command
+// they're documented in the readme. add fields if you like, but don't rename or remove them
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+}
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("output format {} not recognised (expected text or json)", s))
+        }
+    }
+}
+
+pub fn fighter(index: usize, f: &Fighter) -> Value {
+    json!({
+        "index": index,
+        "name": f.name,
+        "owner": f.owner,
+        "class": f.class.to_string(),
+        "strength": f.strength,
+        "speed": f.speed,
+        "skill": f.skill,
+        "unspent_points": f.unspent_points,
+        "total": f.total(),
+        "rating": f.rating,
+        "kills": f.kills,
+        "battles_won": f.battles_won,
+        "battles_fought": f.battles_fought,
+        "dead": f.dead,
+        "pre_matched": f.pre_matched,
+        "previous": f.previous
+    })
+}
+
+pub fn fighters(game: &GameState) -> Value {
+    json!({ "fighters": game.fighters.iter().enumerate().map(|(i, f)| fighter(i, f)).collect::<Vec<Value>>() })
+}
+
+pub fn info(game: &GameState) -> Value {
+    json!({
+        "season_name": game.season_name,
+        "num_rounds": game.num_rounds,
+        "fighters": game.fighters.len(),
+        "living_fighters": game.fighters.iter().filter(|f| !f.dead).count(),
+        "round_scheduled": game.next_round.is_some(),
+        "journal_mode": game.journal_mode,
+        "journal_len": game.journal_len
+    })
+}
+
+pub fn round(r: &RoundReport) -> Value {
+    let matchups: Vec<Value> = r.matchups.iter().map(|m| {
+        let battle = m.battle.as_ref().map(|b| {
+            let winner = match b.result {
+                BattleResult::F1Win | BattleResult::F1WinFromCleric => json!(b.fighter_1),
+                BattleResult::F2Win | BattleResult::F2WinFromCleric => json!(b.fighter_2),
+                BattleResult::Draw | BattleResult::DrawFromCleric => Value::Null
+            };
+            json!({
+                "rolls_1": b.rolls_1,
+                "rolls_2": b.rolls_2,
+                "injury_1": b.injury_1,
+                "injury_2": b.injury_2,
+                "points": b.points,
+                "result": b.result.name(),
+                "winner": winner,
+                "events": b.other_events
+            })
+        });
+        json!({ "fighter_1": m.fighter_1, "fighter_2": m.fighter_2, "battle": battle })
+    }).collect();
+    json!({
+        "kind": "standard", // boss rounds don't exist yet
+        "round_no": r.round_no,
+        "arena": r.arena.to_string(),
+        "modifier": r.modifier.to_string(),
+        "run": r.run,
+        "matchups": matchups,
+        "sitting_out": r.sitting_out
+    })
+}
+
+pub fn edits(edits: &[FighterEdit]) -> Value {
+    json!({ "edits": edits })
+}
+
+pub fn history(game: &GameState) -> Value {
+    let len = game.history().len();
+    let list: Vec<Value> = game.history().iter().enumerate().map(|(i, e)| json!({
+        "undo_steps": len - i, // how many undos it takes to get back there
+        "time": e.time,
+        "command": e.command
+    })).collect();
+    json!({ "history": list })
+}
+
+pub fn journal(game: &GameState) -> Value {
+    let list: Vec<Value> = game.journal().iter().map(|e| json!({ "seq": e.seq, "time": e.time, "command": e.action.to_string() })).collect();
+    json!({ "journal_mode": game.journal_mode, "entries": list })
+}
+
+pub fn lineage(seasons: &[(String, usize, Fighter)]) -> Value { // newest season first
+    json!({ "lineage": seasons.iter().map(|(s, i, f)| json!({ "season_name": s, "fighter": fighter(*i, f) })).collect::<Vec<Value>>() })
+}
+
+pub fn save(index: usize, s: &SaveEntry) -> Value {
+    json!({
+        "index": index,
+        "name": s.name,
+        "alias": s.alias,
+        "season_name": s.season_name,
+        "path": s.path,
+        "created": s.created,
+        "last_modified": s.last_modified,
+        "archived": s.archived,
+        "missing": !Path::new(&s.path).exists()
+    })
+}
+
+pub fn saves(saves: &[SaveEntry]) -> Value {
+    json!({ "saves": saves.iter().enumerate().map(|(i, s)| save(i, s)).collect::<Vec<Value>>() })
+}
+
+pub fn problems(problems: &[Problem]) -> Value {
+    let list: Vec<Value> = problems.iter().map(|p| match &p.fix {
+        Fix::Auto(d, fixed) => json!({ "explanation": p.explanation, "fix": d, "automatic": true, "fixed": fixed }),
+        Fix::Manual(d) => json!({ "explanation": p.explanation, "fix": d, "automatic": false, "fixed": false })
+    }).collect();
+    json!({ "problems": list })
+}
+
+pub fn hall_of_fame(h: &HallOfFame) -> Value {
+    let owners: Vec<Value> = h.owner_championships().into_iter().map(|(o, n)| json!({ "owner": o, "championships": n })).collect();
+    json!({
+        "careers": h.careers,
+        "owners": owners,
+        "skipped": h.skipped
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighter::Class;
+    use crate::testing::{season, play};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_json_shapes() { // these are promised to bots, so changing them should fail a test
+        let mut game = GameState::new_game("json");
+        game.fighters.push(Fighter::new(String::from("a"), String::from("o"), Class::Tank, 1, 2, 3));
+        let v = fighters(&game);
+        let f = &v["fighters"][0];
+        for key in ["index", "name", "owner", "class", "strength", "speed", "skill", "unspent_points", "total", "rating", "kills", "battles_won", "battles_fought", "dead"] {
+            assert!(f.get(key).is_some(), "fighter is missing {}", key)
+        }
+        assert_eq!(f["class"], json!("chicken")); // display names, not variant names
+        assert_eq!(info(&game)["season_name"], json!("json"));
+        assert_eq!("JSON".parse::<Format>(), Ok(Format::Json));
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_battle_result() { // named explicitly, not the enum's debug name
+        let mut game = season("json", &["a", "b"]);
+        play(&mut game, 1, &mut ChaCha8Rng::seed_from_u64(1));
+        let v = round(&game.round_report(0).unwrap());
+        let result = v["matchups"][0]["battle"]["result"].as_str().unwrap().to_string();
+        assert!(["fighter_1", "fighter_2", "draw"].contains(&result.as_str()), "unexpected result {}", result);
+    }
+}
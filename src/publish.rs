@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+use crate::game::GameState;
+use crate::round::{Arena, Modifier};
+use crate::report::{RoundReport, MatchReport, FighterRef, FighterRow};
+use crate::utils::{fmt_vec, fmt_option};
+use crate::season;
+use crate::error::GameError;
+
+// a static website for a season: standings, a page per round, a page per fighter and a glossary. plain html files with
+// the css inlined, so the folder can go on any static host as is
+
+const CSS: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; color: #222; background: #fafafa; }
+nav a { margin-right: 1em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+th { background: #eee; }
+.dead { color: #999; text-decoration: line-through; }
+details { margin: 0.5em 0 1.5em; }
+summary { cursor: pointer; color: #555; }
+pre { background: #eee; padding: 0.5em; }";
+
+pub fn escape(s: &str) -> String { // fighter names are whatever the players typed, so they can't be trusted in html
+    let mut ret = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&#39;"),
+            c => ret.push(c)
+        }
+    }
+    ret
+}
+
+fn page(game: &GameState, title: &str, body: &str) -> String {
+    format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0} - {1}</title>\n<style>\n{2}\n</style>\n</head>\n<body>\n<nav><a href=\"index.html\">standings</a><a href=\"glossary.html\">glossary</a></nav>\n<h1>{1}</h1>\n{3}\n</body>\n</html>\n",
+        escape(title), escape(&game.season_name), CSS, body)
+}
+
+fn fighter_link(f: &FighterRef) -> String {
+    format!("<a href=\"fighter-{}.html\">{}</a>", f.index, escape(&f.name))
+}
+
+fn round_file(round_no: i32) -> String {
+    format!("round-{}.html", round_no)
+}
+
+fn index_page(game: &GameState, rounds: &[RoundReport]) -> String {
+    let mut rows: Vec<FighterRow> = game.fighter_table().rows;
+    rows.sort_by_key(|r| (r.dead, -r.rating, -r.kills)); // living fighters first, best rating at the top
+    let mut body = String::from("<h2>standings</h2>\n<table>\n<tr><th>#</th><th>name</th><th>owner</th><th>class</th><th>strength</th><th>speed</th><th>skill</th><th>total</th><th>rating</th><th>kills</th><th>won</th></tr>\n");
+    for (i, r) in rows.iter().enumerate() {
+        body.push_str(&format!("<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}/{}</td></tr>\n",
+            if r.dead { " class=\"dead\"" } else { "" }, i + 1, fighter_link(&FighterRef { index: r.index, name: r.name.clone() }), escape(&r.owner), r.class,
+            r.strength, r.speed, r.skill, r.total, r.rating, r.kills, game.fighters[r.index].battles_won, game.fighters[r.index].battles_fought));
+    }
+    body.push_str("</table>\n<h2>rounds</h2>\n<ul>\n");
+    for r in rounds {
+        body.push_str(&format!("<li><a href=\"{}\">round {}</a>: {}, {}</li>\n", round_file(r.round_no), r.round_no, r.arena, r.modifier))
+    }
+    if rounds.is_empty() {
+        body.push_str("<li>no rounds played yet</li>\n")
+    }
+    body.push_str("</ul>\n");
+    page(game, "standings", &body)
+}
+
+fn match_html(m: &MatchReport) -> String {
+    let mut ret = format!("<h3>{} VS {}</h3>\n", fighter_link(&m.fighter_1), fighter_link(&m.fighter_2));
+    let b = match &m.battle {
+        Some(b) => b,
+        None => return ret
+    };
+    // results are folded away, same idea as the spoilers in the discord style
+    ret.push_str(&format!("<details>\n<summary>results</summary>\n<pre>{} VS {}</pre>\n", fmt_vec(&b.rolls_1), fmt_vec(&b.rolls_2)));
+    ret.push_str(&format!("<p>injuries: {} {}, {} {}</p>\n", escape(&m.fighter_1.name), fmt_option(&b.injury_1), escape(&m.fighter_2.name), fmt_option(&b.injury_2)));
+    for e in &b.other_events {
+        ret.push_str(&format!("<p>{}</p>\n", escape(e)))
+    }
+    match m.winner() {
+        Some(w) => ret.push_str(&format!("<p>winner: <b>{}</b></p>\n", fighter_link(w))),
+        None => ret.push_str("<p><b>draw!</b></p>\n")
+    }
+    ret.push_str("</details>\n");
+    ret
+}
+
+fn round_page(game: &GameState, rounds: &[RoundReport], i: usize) -> String {
+    let r = &rounds[i];
+    let mut body = format!("<h2>round {}</h2>\n<p>arena: <a href=\"glossary.html#arena-{}\">{}</a><br>\nmodifier: <a href=\"glossary.html#modifier-{}\">{}</a></p>\n",
+        r.round_no, i_of(Arena::iter(), &r.arena), r.arena, i_of(Modifier::iter(), &r.modifier), r.modifier);
+    for m in &r.matchups {
+        body.push_str(&match_html(m))
+    }
+    if let Some(s) = &r.sitting_out {
+        body.push_str(&format!("<p><i>{} sits out</i></p>\n", fighter_link(s)))
+    }
+    body.push_str("<p>");
+    if i > 0 {
+        body.push_str(&format!("<a href=\"{}\">previous round</a> ", round_file(rounds[i - 1].round_no)))
+    }
+    if let Some(next) = rounds.get(i + 1) {
+        body.push_str(&format!("<a href=\"{}\">next round</a>", round_file(next.round_no)))
+    }
+    body.push_str("</p>\n");
+    page(game, &format!("round {}", r.round_no), &body)
+}
+
+fn i_of<T: PartialEq>(mut all: impl Iterator<Item = T>, x: &T) -> usize { // for glossary anchors
+    all.position(|a| a == *x).unwrap_or(0)
+}
+
+fn fighter_page(game: &GameState, rounds: &[RoundReport], index: usize) -> String {
+    let f = &game.fighters[index];
+    let mut body = format!("<h2{}>{}</h2>\n<p>owned by {}. {}{}</p>\n", if f.dead { " class=\"dead\"" } else { "" }, escape(&f.name), escape(&f.owner), f.class, if f.dead { ", dead" } else { "" });
+    body.push_str(&format!("<table>\n<tr><th>strength</th><th>speed</th><th>skill</th><th>total</th><th>unspent points</th><th>rating</th><th>kills</th><th>won</th></tr>\n<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}/{}</td></tr>\n</table>\n",
+        f.strength, f.speed, f.skill, f.total(), f.unspent_points, f.rating, f.kills, f.battles_won, f.battles_fought));
+
+    body.push_str("<h2>this season</h2>\n<table>\n<tr><th>round</th><th>opponent</th><th>result</th><th>injury</th></tr>\n");
+    let mut fought = 0;
+    for r in rounds {
+        for m in &r.matchups {
+            let (opponent, injury) = match &m.battle {
+                Some(b) if m.fighter_1.index == index => (&m.fighter_2, b.injury_1),
+                Some(b) if m.fighter_2.index == index => (&m.fighter_1, b.injury_2),
+                _ => continue
+            };
+            let result = match m.winner() {
+                Some(w) if w.index == index => "won",
+                Some(_) => "lost",
+                None => "draw"
+            };
+            body.push_str(&format!("<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>\n", round_file(r.round_no), r.round_no, fighter_link(opponent), result, fmt_option(&injury)));
+            fought += 1
+        }
+    }
+    body.push_str("</table>\n");
+    if fought == 0 {
+        body.push_str("<p>no battles yet</p>\n")
+    }
+
+    if f.previous.is_some() {
+        body.push_str("<h2>past seasons</h2>\n");
+        match season::lineage(game, index) {
+            Ok(l) => {
+                body.push_str("<table>\n<tr><th>season</th><th>name</th><th>rating</th><th>kills</th><th>won</th></tr>\n");
+                for (s, _, p) in l.iter().skip(1) { // the first one is this season
+                    body.push_str(&format!("<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}/{}</td></tr>\n", escape(s), escape(&p.name), p.rating, p.kills, p.battles_won, p.battles_fought))
+                }
+                body.push_str("</table>\n")
+            }
+            Err(e) => body.push_str(&format!("<p>couldn't follow them back: {}</p>\n", escape(&e.to_string()))) // old saves moved or deleted, no need to fail the whole site over it
+        }
+    }
+    page(game, &f.name, &body)
+}
+
+fn glossary_page(game: &GameState) -> String {
+    let mut body = String::from("<h2>arenas</h2>\n<dl>\n");
+    for (i, a) in Arena::iter().enumerate() {
+        body.push_str(&format!("<dt id=\"arena-{}\"><b>{}</b></dt><dd>{}</dd>\n", i, a, a.description()))
+    }
+    body.push_str("</dl>\n<h2>modifiers</h2>\n<dl>\n");
+    for (i, m) in Modifier::iter().enumerate() {
+        body.push_str(&format!("<dt id=\"modifier-{}\"><b>{}</b></dt><dd>{}</dd>\n", i, m, m.description()))
+    }
+    body.push_str("</dl>\n");
+    page(game, "glossary", &body)
+}
+
+pub fn publish(game: &GameState, dir: &str) -> Result<Vec<String>, GameError> { // writes the whole site into dir, and hands back the files it wrote
+    if let Err(e) = fs::create_dir_all(dir) {
+        return Err(GameError::Io(format!("could not create {}: {}", dir, e)))
+    }
+    let rounds: Vec<RoundReport> = (0..game.prev_rounds.len()).filter_map(|i| game.round_report(i).ok()).collect();
+    let mut pages = vec![(String::from("index.html"), index_page(game, &rounds)), (String::from("glossary.html"), glossary_page(game))];
+    for i in 0..rounds.len() {
+        pages.push((round_file(rounds[i].round_no), round_page(game, &rounds, i)))
+    }
+    for i in 0..game.fighters.len() {
+        pages.push((format!("fighter-{}.html", i), fighter_page(game, &rounds, i)))
+    }
+
+    let mut written = Vec::new();
+    for (name, html) in pages {
+        let path = Path::new(dir).join(&name).to_string_lossy().to_string();
+        if let Err(e) = fs::write(&path, html) {
+            return Err(GameError::Io(format!("could not write {}: {}", path, e)))
+        }
+        written.push(path)
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{season, play, temp_path};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn published(name: &str) -> (GameState, String, Vec<String>) { // two rounds of three fighters, published into their own folder
+        let mut game = season("site", &["<script>", "bob", "al"]);
+        play(&mut game, 2, &mut ChaCha8Rng::seed_from_u64(5));
+        let dir = temp_path(name);
+        let files = publish(&game, &dir).unwrap();
+        (game, dir, files)
+    }
+
+    fn page(dir: &str, name: &str) -> String {
+        fs::read_to_string(Path::new(dir).join(name)).unwrap()
+    }
+
+    #[test]
+    fn test_files() {
+        let (_, dir, files) = published("publish_files");
+        assert_eq!(files.len(), 2 + 2 + 3); // index, glossary, rounds, fighters
+        assert!(files.iter().all(|f| Path::new(f).exists()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_escaping() {
+        let (_, dir, _) = published("publish_escaping");
+        let index = page(&dir, "index.html");
+        assert!(index.contains("&lt;script&gt;") && !index.contains("<script>"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_links() {
+        let (game, dir, _) = published("publish_links");
+        assert!(page(&dir, "index.html").contains("<a href=\"round-2.html\">round 2</a>"));
+        let round = page(&dir, "round-1.html");
+        assert!(round.contains("<details>") && round.contains("glossary.html#arena-") && round.contains("next round"));
+        let fighter = page(&dir, "fighter-1.html");
+        assert_eq!(fighter.matches("<tr><td><a href=\"round-").count() as i32, game.fighters[1].battles_fought);
+        assert!(page(&dir, "glossary.html").contains("crocodile pit"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
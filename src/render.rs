@@ -0,0 +1,321 @@
+use serde::{Serialize, Deserialize};
+use std::str::FromStr;
+
+use crate::report::{RoundReport, MatchReport, FighterTable};
+use crate::utils::{fmt_vec, fmt_option};
+use crate::game::GameState;
+use crate::template::{Template, round_context, standings_context};
+use crate::error::GameError;
+
+// round reports for posting somewhere other than a terminal. the plain text style is just RoundReport's Display
+
+pub const DISCORD_LIMIT: usize = 2000; // characters per message
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    #[default]
+    Text,
+    Discord,
+    BBCode,
+}
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Style, String> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Style::Text),
+            "discord" | "markdown" | "md" => Ok(Style::Discord),
+            "bbcode" => Ok(Style::BBCode),
+            _ => Err(format!("style {} not recognised (expected text, discord or bbcode)", s))
+        }
+    }
+}
+
+pub enum Layout { // a built in style, or one of the user's templates
+    Style(Style),
+    Template(Template),
+}
+
+impl Layout {
+    pub fn pick(style: Option<&str>, template: Option<&str>, config_template: &str, fallback: Style) -> Result<Layout, GameError> { // --style, --template, the config's template, then fallback
+        match (style, template) {
+            (Some(_), Some(_)) => Err(GameError::Parse(String::from("use --style or --template, not both"))),
+            (Some(s), None) => Ok(Layout::Style(s.parse::<Style>().map_err(GameError::Parse)?)),
+            (None, Some(t)) => Ok(Layout::Template(Template::load(t)?)),
+            (None, None) if !config_template.is_empty() => Ok(Layout::Template(Template::load(config_template)?)),
+            (None, None) => Ok(Layout::Style(fallback))
+        }
+    }
+
+    pub fn round(&self, r: &RoundReport, season: &str) -> Vec<String> {
+        match self {
+            Layout::Style(s) => render_round(r, *s),
+            Layout::Template(t) => vec![t.render(&round_context(r, season))]
+        }
+    }
+
+    pub fn standings(&self, game: &GameState) -> String {
+        match self {
+            Layout::Style(s) => render_fighters(&game.fighter_table(), *s),
+            Layout::Template(t) => t.render(&standings_context(game))
+        }
+    }
+}
+
+pub fn render_round(r: &RoundReport, style: Style) -> Vec<String> { // one string per message. only discord has a limit, so text is always one
+    match style {
+        Style::Text => vec![r.to_string()],
+        Style::Discord => split_chunks(&discord_sections(r), DISCORD_LIMIT),
+        Style::BBCode => vec![bbcode_round(r)]
+    }
+}
+
+pub fn render_fighters(t: &FighterTable, style: Style) -> String {
+    match style {
+        Style::Text => t.to_string(),
+        Style::Discord => format!("```\n{}\n```", t), // discord has no tables, but the text one lines up fine in a code block
+        Style::BBCode => bbcode_fighters(t)
+    }
+}
+
+pub fn join_chunks(chunks: &[String]) -> String { // for printing or writing to a file, with a marker where each new message starts
+    let mut ret = String::new();
+    for (i, c) in chunks.iter().enumerate() {
+        if chunks.len() > 1 {
+            ret.push_str(&format!("-- message {} of {} --\n", i + 1, chunks.len()))
+        }
+        ret.push_str(c);
+        if i + 1 < chunks.len() {
+            ret.push_str("\n\n")
+        }
+    }
+    ret
+}
+
+fn escape_markdown(s: &str) -> String { // so a fighter called *bob* doesn't come out bold
+    let mut ret = String::new();
+    for c in s.chars() {
+        if "\\*_~`|#>[]".contains(c) {
+            ret.push('\\')
+        }
+        ret.push(c)
+    }
+    ret
+}
+
+fn escape_bbcode(s: &str) -> String { // bbcode has no escape character, but forums show these entities as brackets, so a fighter called [b]ob can't open a tag
+    s.replace('[', "&#91;").replace(']', "&#93;")
+}
+
+fn discord_sections(r: &RoundReport) -> Vec<String> { // the heading, then one section per matchup. chunks only ever split between these
+    let mut sections = vec![format!("## round {}\n**arena:** {}\n**modifier:** {}", r.round_no, r.arena, r.modifier)];
+    for m in &r.matchups {
+        sections.push(discord_match(m))
+    }
+    if let Some(s) = &r.sitting_out {
+        sections.push(format!("*{} sits out*", escape_markdown(&s.name)))
+    }
+    sections
+}
+
+fn discord_match(m: &MatchReport) -> String {
+    let (f1, f2) = (escape_markdown(&m.fighter_1.name), escape_markdown(&m.fighter_2.name));
+    let mut ret = format!("### {} VS {}", f1, f2);
+    let b = match &m.battle {
+        Some(b) => b,
+        None => return ret // not run yet, so no results to hide
+    };
+    // the whole result goes in one spoiler, so each fight can be revealed on its own
+    ret.push_str(&format!("\n||```\n{} VS {}\n```\n", fmt_vec(&b.rolls_1), fmt_vec(&b.rolls_2)));
+    ret.push_str(&format!("injuries: {} {}, {} {}", f1, fmt_option(&b.injury_1), f2, fmt_option(&b.injury_2)));
+    for e in &b.other_events {
+        ret.push_str(&format!("\n{}", escape_markdown(e)))
+    }
+    match m.winner() {
+        Some(w) => ret.push_str(&format!("\nwinner: **{}**||", escape_markdown(&w.name))),
+        None => ret.push_str("\n**draw!**||")
+    }
+    ret
+}
+
+fn bbcode_round(r: &RoundReport) -> String { // same layout as discord, but forums have no message limit
+    let mut ret = format!("[b]round {}[/b]\n[b]arena:[/b] {}\n[b]modifier:[/b] {}", r.round_no, r.arena, r.modifier);
+    for m in &r.matchups {
+        let (f1, f2) = (escape_bbcode(&m.fighter_1.name), escape_bbcode(&m.fighter_2.name));
+        ret.push_str(&format!("\n\n[b]{} VS {}[/b]", f1, f2));
+        let b = match &m.battle {
+            Some(b) => b,
+            None => continue
+        };
+        ret.push_str(&format!("\n[spoiler]rolls: [code]{} VS {}[/code]", fmt_vec(&b.rolls_1), fmt_vec(&b.rolls_2)));
+        ret.push_str(&format!("\ninjuries: {} {}, {} {}", f1, fmt_option(&b.injury_1), f2, fmt_option(&b.injury_2)));
+        for e in &b.other_events {
+            ret.push_str(&format!("\n{}", escape_bbcode(e)))
+        }
+        match m.winner() {
+            Some(w) => ret.push_str(&format!("\nwinner: [b]{}[/b][/spoiler]", escape_bbcode(&w.name))),
+            None => ret.push_str("\n[b]draw![/b][/spoiler]")
+        }
+    }
+    if let Some(s) = &r.sitting_out {
+        ret.push_str(&format!("\n\n[i]{} sits out[/i]", escape_bbcode(&s.name)))
+    }
+    ret
+}
+
+fn bbcode_fighters(t: &FighterTable) -> String {
+    let mut ret = String::from("[table]\n[tr][th]#[/th][th]name[/th][th]owner[/th][th]class[/th][th]strength[/th][th]speed[/th][th]skill[/th][th]points[/th][th]total[/th][th]rating[/th][th]kills[/th][/tr]");
+    for r in &t.rows {
+        let name = if r.dead { format!("[s]{}[/s] (dead)", escape_bbcode(&r.name)) } else { format!("[b]{}[/b]", escape_bbcode(&r.name)) };
+        ret.push_str(&format!("\n[tr][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][td]{}[/td][/tr]",
+            r.index, name, escape_bbcode(&r.owner), r.class, r.strength, r.speed, r.skill, r.unspent_points, r.total, r.rating, r.kills))
+    }
+    ret.push_str("\n[/table]");
+    ret
+}
+
+pub fn split_chunks(sections: &[String], limit: usize) -> Vec<String> { // packs sections into as few messages as fit. a section too big on its own gets split by line
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let pieces = sections.iter().flat_map(|s| {
+        if s.chars().count() <= limit {
+            vec![s.clone()]
+        } else {
+            split_long(s, limit)
+        }
+    });
+    for p in pieces {
+        let sep = if current.is_empty() { 0 } else { 2 }; // blank line between sections
+        if current.chars().count() + sep + p.chars().count() > limit {
+            chunks.push(std::mem::take(&mut current))
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n")
+        }
+        current.push_str(&p)
+    }
+    if !current.is_empty() {
+        chunks.push(current)
+    }
+    chunks
+}
+
+fn split_long(s: &str, limit: usize) -> Vec<String> { // last resort, this does break up spoilers
+    let mut ret: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for line in s.lines() {
+        for piece in line.chars().collect::<Vec<char>>().chunks(limit).map(|c| c.iter().collect::<String>()) { // lines longer than a whole message, somehow
+            if !current.is_empty() && current.chars().count() + 1 + piece.chars().count() > limit {
+                ret.push(std::mem::take(&mut current))
+            }
+            if !current.is_empty() {
+                current.push('\n')
+            }
+            current.push_str(&piece)
+        }
+    }
+    if !current.is_empty() {
+        ret.push(current)
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::round::Pairing;
+    use crate::testing::{season, play, temp_path};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn played(fighters: &[&str]) -> (GameState, RoundReport) { // one round run, and its report
+        let mut game = season("render", fighters);
+        play(&mut game, 1, &mut ChaCha8Rng::seed_from_u64(3));
+        let report = game.round_report(0).unwrap();
+        (game, report)
+    }
+
+    #[test]
+    fn test_discord_round() {
+        let names: Vec<String> = (0..40).map(|i| format!("fighter_{}", i)).collect();
+        let mut game = season("render", &names);
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let scheduled = game.new_round(None, None, Pairing::Random, &mut rng).unwrap();
+        let text = render_round(&scheduled, Style::Discord).join("\n\n");
+        assert!(text.starts_with("## round 1\n**arena:** ") && !text.contains("||"));
+        assert!(text.contains("### fighter\\_")); // underscores escaped
+
+        let run = game.run_round(&mut rng).unwrap();
+        let chunks = render_round(&run, Style::Discord);
+        assert!(chunks.len() > 1 && chunks.iter().all(|c| c.chars().count() <= DISCORD_LIMIT));
+        assert!(chunks.iter().all(|c| c.matches("||").count() % 2 == 0)); // no spoiler split across messages
+        assert_eq!(chunks.iter().map(|c| c.matches("### ").count()).sum::<usize>(), 20);
+        assert_eq!(render_round(&run, Style::Text), vec![run.to_string()]);
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        let big = vec![String::from("a\n").repeat(10), String::from("b").repeat(25)];
+        let split = split_chunks(&big, 10);
+        assert!(split.iter().all(|c| c.chars().count() <= 10));
+        assert_eq!(split.concat().replace('\n', ""), format!("{}{}", "a".repeat(10), "b".repeat(25)));
+    }
+
+    #[test]
+    fn test_style_names() {
+        assert_eq!("MD".parse::<Style>(), Ok(Style::Discord));
+        assert_eq!("bbcode".parse::<Style>(), Ok(Style::BBCode));
+        assert!("html".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn test_bbcode_round() {
+        let (_, run) = played(&["a", "b", "c"]);
+        let chunks = render_round(&run, Style::BBCode);
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].starts_with("[b]round 1[/b]\n[b]arena:[/b] "));
+        assert_eq!(chunks[0].matches("[spoiler]").count(), 1);
+        assert!(chunks[0].contains("[/spoiler]\n\n[i]") && chunks[0].ends_with(" sits out[/i]"));
+    }
+
+    #[test]
+    fn test_bbcode_table() {
+        let (game, _) = played(&["a", "b", "c"]);
+        let table = render_fighters(&game.fighter_table(), Style::BBCode);
+        assert_eq!(table.lines().count(), 6); // [table], headings, 3 fighters, [/table]
+        assert!(table.lines().nth(2).unwrap().starts_with("[tr][td]0[/td][td][b]a[/b][/td][td]o[/td]"));
+        assert!(render_fighters(&game.fighter_table(), Style::Discord).starts_with("```\n"));
+    }
+
+    #[test]
+    fn test_bbcode_escape() {
+        let mut game = season("render", &["[b]ob", "[/table]", "c"]);
+        for f in &mut game.fighters {
+            f.owner = String::from("[url=x]o[/url]")
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        play(&mut game, 1, &mut rng);
+        let round = render_round(&game.round_report(0).unwrap(), Style::BBCode).concat();
+        let table = render_fighters(&game.fighter_table(), Style::BBCode);
+        for text in [&round, &table] {
+            assert!(!text.contains("[b]ob") && !text.contains("[/table]\n[tr]") && !text.contains("[url"));
+        }
+        assert!(table.contains("[b]&#91;b&#93;ob[/b]") && table.contains("&#91;url=x&#93;o&#91;/url&#93;"));
+        assert_eq!(table.matches("[/table]").count(), 1);
+    }
+
+    #[test]
+    fn test_log_dir() {
+        let (game, _) = played(&["a", "b"]);
+        let dir = temp_path("log_dir");
+        let mut config = crate::config::Config::default();
+        config.set("log_dir", &dir).unwrap(); // fine to set before it exists
+        let layout = Layout::Style(Style::Text);
+        assert!(matches!(game.log_round(0, None, &layout, &config), Err(GameError::Io(_))));
+        std::fs::create_dir(&dir).unwrap();
+        let written = game.log_round(0, None, &layout, &config).unwrap();
+        assert!(std::path::Path::new(&written).starts_with(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
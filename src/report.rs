@@ -0,0 +1,269 @@
+use serde::Serialize;
+use std::fmt;
+use pad::{PadStr, Alignment};
+
+use crate::round::{Arena, Modifier};
+use crate::batlog::Battle;
+use crate::battle::BattleResult;
+use crate::fighter::{Fighter, Class};
+use crate::edit::FighterEdit;
+use crate::check::Problem;
+use crate::utils::{fmt_vec, fmt_option, fmt_vec_with_tabs};
+
+// plain data for front ends to show however they like. the Display impls are the text the cli prints
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FighterRef {
+    pub index: usize,
+    pub name: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MatchReport {
+    pub fighter_1: FighterRef,
+    pub fighter_2: FighterRef,
+    pub battle: Option<Battle>, // only once the round has been run
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RoundReport {
+    pub round_no: i32,
+    pub arena: Arena,
+    pub modifier: Modifier,
+    pub run: bool,
+    pub matchups: Vec<MatchReport>,
+    pub sitting_out: Option<FighterRef>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FighterRow {
+    pub index: usize,
+    pub name: String,
+    pub owner: String,
+    pub class: Class,
+    pub strength: i32,
+    pub speed: i32,
+    pub skill: i32,
+    pub unspent_points: i32,
+    pub total: i32,
+    pub rating: i32,
+    pub kills: i32,
+    pub dead: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FighterTable {
+    pub rows: Vec<FighterRow>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Report { // what an action did
+    Nothing,
+    StatsAdded { fighter: String, stats: [i32; 3] },
+    FightersAdded(Vec<FighterRef>),
+    Edited(Vec<FighterEdit>), // empty if every new value was the same as the old one
+    MatchArranged { fighter_1: FighterRef, fighter_2: FighterRef },
+    RoundScheduled(RoundReport),
+    RoundRun(RoundReport),
+    RoundCancelled(i32), // round number
+    Undone(Vec<String>), // commands, newest first
+    Repaired(Vec<Problem>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub changed: bool,
+    pub report: Report,
+}
+
+impl MatchReport {
+    pub fn winner(&self) -> Option<&FighterRef> { // None for a draw, or if it hasn't been fought yet
+        match self.battle.as_ref().map(|b| &b.result) {
+            Some(BattleResult::F1Win | BattleResult::F1WinFromCleric) => Some(&self.fighter_1),
+            Some(BattleResult::F2Win | BattleResult::F2WinFromCleric) => Some(&self.fighter_2),
+            _ => None
+        }
+    }
+
+    pub fn battle_text(&self) -> String { // results of the battle, empty if it hasn't happened
+        let battle = match &self.battle {
+            Some(b) => b,
+            None => return String::new()
+        };
+        let (f1name, f2name) = (&self.fighter_1.name, &self.fighter_2.name);
+        let mut ret = String::new();
+        ret.push_str(&format!("\t\trolls:\n\t\t\t{} VS {}\n", fmt_vec(&battle.rolls_1), fmt_vec(&battle.rolls_2)));
+        ret.push_str(&format!("\t\tinjuries:\n\t\t\t{}: {}\n\t\t\t{}: {}\n", f1name, fmt_option(&battle.injury_1), f2name, fmt_option(&battle.injury_2))); // lotsa tabs
+        ret.push_str(&format!("\t\tother events:\n{}", fmt_vec_with_tabs(&battle.other_events, 3)));
+
+        type Res = BattleResult; // arguably makes code easier to read i guess???
+        match battle.result {
+            Res::F1Win | Res::F1WinFromCleric => {
+                ret.push_str(&format!("\t\twinner: {}", f1name))
+            }
+            Res::F2Win | Res::F2WinFromCleric => {
+                ret.push_str(&format!("\t\twinner: {}", f2name))
+            }
+            Res::Draw | Res::DrawFromCleric => {
+                ret.push_str("\t\tdraw!")
+            }
+        }
+        ret
+    }
+}
+
+impl fmt::Display for RoundReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "round {}\narena: {}\nmodifier: {}\nmatchups:\n", self.round_no, self.arena, self.modifier)?;
+        for m in &self.matchups {
+            write!(f, "\t{} VS {}\n{}", m.fighter_1.name, m.fighter_2.name, m.battle_text())?
+        }
+        if let Some(s) = &self.sitting_out {
+            write!(f, "\t{} sits out", s.name)?
+        }
+        Ok(())
+    }
+}
+
+impl FighterRow {
+    pub fn new(index: usize, f: &Fighter) -> Self {
+        FighterRow {
+            index,
+            name: f.name.clone(),
+            owner: f.owner.clone(),
+            class: f.class.clone(),
+            strength: f.strength,
+            speed: f.speed,
+            skill: f.skill,
+            unspent_points: f.unspent_points,
+            total: f.total(),
+            rating: f.rating,
+            kills: f.kills,
+            dead: f.dead
+        }
+    }
+}
+
+const TABLE_SEP: &str = " | "; // avoid magic numbers
+
+impl fmt::Display for FighterTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let longest_name_len = self.rows.iter().map(|r| r.name.len()).fold(4, usize::max); // length of "name"
+        let longest_owner_len = self.rows.iter().map(|r| r.owner.len()).fold(5, usize::max); // length of "owner"
+        let index_pad_amt = match self.rows.last() {
+            Some(r) => r.index.to_string().len(), // ew
+            None => 0
+        };
+
+        let name_head = "name".pad_to_width(longest_name_len); // pad out headings
+        let owner_head = "owner".pad_to_width(longest_owner_len);
+
+        // class is done lazily cuz it's a discrete thing
+        // class as string will never be longer than 8
+        // if this fact ever changes FIX THIS
+        write!(f, "{3}{1}{0}{2}{0}class   {0}strength{0}speed{0}skill{0}points{0}total{0}rating{0}kills", TABLE_SEP, name_head, owner_head, " ".repeat(index_pad_amt + 3))?;
+
+        for r in &self.rows {
+            let name_pad = r.name.pad_to_width_with_alignment(longest_name_len, Alignment::Right); // pad out names etc
+            let owner_pad = r.owner.pad_to_width_with_alignment(longest_owner_len, Alignment::Right);
+            let class_pad = r.class.to_string().pad_to_width_with_alignment(8, Alignment::Right); // should possibly eliminate a magic number here
+            let index_pad = r.index.to_string().pad_to_width_with_alignment(index_pad_amt, Alignment::Right);
+
+            // name owner class st sp sk us tt rt kl
+            write!(f, "\n[{9}] {1}{0}{2}{0}{3}{0}{4:>8}{0}{5:>5}{0}{6:>5}{0}{11:>6}{0}{10:>5}{0}{7:>6}{0}{8:>5}",
+                TABLE_SEP, name_pad, owner_pad, class_pad, r.strength, r.speed, r.skill, r.rating, r.kills, index_pad, r.total, r.unspent_points)?; // the Worst format string
+            if r.dead {
+                write!(f, "  (dead)")?
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::journal::Action;
+    use crate::round::{Round, Pairing};
+    use crate::error::GameError;
+    use crate::testing::{season, play};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_fighter_table() {
+        let game = season("reports", &["a", "bb", "c"]);
+        let table = game.fighter_table().to_string();
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.lines().nth(2).unwrap().starts_with("[1]   bb | "));
+    }
+
+    #[test]
+    fn test_round_reports() {
+        let mut game = season("reports", &["a", "bb", "c"]);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let scheduled = game.new_round(None, None, Pairing::Random, &mut rng).unwrap();
+        assert!(!scheduled.run && scheduled.matchups.iter().all(|m| m.battle.is_none()));
+        assert_eq!(scheduled.sitting_out.as_ref().map(|s| s.name.is_empty()), Some(false));
+
+        let run = game.run_round(&mut rng).unwrap();
+        assert_eq!(run.matchups[0].battle.as_ref().map(|b| b.fighter_1), Some(run.matchups[0].fighter_1.index));
+        assert_eq!(game.round_report(0).unwrap().to_string(), run.to_string());
+        assert!(run.to_string().starts_with("round 1\narena: "));
+    }
+
+    #[test]
+    fn test_preview() { // run-round --dry-run --seed, then the real thing with the same seed
+        let mut game = season("reports", &["a", "bb", "c"]);
+        game.new_round(None, None, Pairing::Random, &mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        let preview = game.preview_round(42).unwrap();
+        assert!(preview.run && game.next_round.is_some() && game.num_rounds == 0 && game.prev_rounds.is_empty());
+        match game.apply(&mut Action::RunRound { seed: 42, round: None }).unwrap().report {
+            Report::RoundRun(r) => assert_eq!(r.to_string(), preview.to_string()),
+            other => panic!("expected a round, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut game = season("reports", &["a", "bb", "c"]);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        assert_eq!(game.run_round(&mut rng).unwrap_err(), GameError::NoRoundScheduled);
+        assert_eq!(game.apply(&mut Action::CancelRound).unwrap_err(), GameError::NoRoundScheduled);
+        assert_eq!(game.apply(&mut Action::AddStats { fighter: 9, stats: [1, 0, 0] }).unwrap_err(), GameError::FighterOutOfRange(9));
+        play(&mut game, 1, &mut rng);
+        assert_eq!(game.round_report(1).unwrap_err(), GameError::RoundOutOfRange(1));
+    }
+
+    #[test]
+    fn test_bad_matchup() { // eg a hand edited save. an error, not a panic
+        let mut game = season("reports", &["a", "bb"]);
+        game.new_round(None, None, Pairing::Random, &mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        if let Some(Round::Standard(r)) = &mut game.next_round {
+            r.matchups = vec![(0, 9)]
+        }
+        assert_eq!(game.preview_round(42).unwrap_err(), GameError::FighterOutOfRange(9));
+        assert_eq!(game.run_round(&mut ChaCha8Rng::seed_from_u64(1)).unwrap_err(), GameError::FighterOutOfRange(9));
+        assert!(game.next_round.is_some() && game.prev_rounds.is_empty() && game.fighters.iter().all(|f| f.battles_fought == 0));
+
+        game.next_round = None;
+        game.pre_matches = vec![(1, 5)];
+        game.new_round(None, None, Pairing::Random, &mut ChaCha8Rng::seed_from_u64(7)).unwrap();
+        assert_eq!(game.run_round(&mut ChaCha8Rng::seed_from_u64(1)).unwrap_err(), GameError::FighterOutOfRange(5));
+    }
+
+    #[test]
+    fn test_boss_rounds() { // not implemented, but a save with one shouldn't bring the program down
+        let mut game = GameState::new_game("boss");
+        let boss: crate::boss::BossRound = serde_json::from_value(serde_json::json!({ "ver": 1, "matchups": [] })).unwrap();
+        game.next_round = Some(Round::Boss(boss.clone()));
+        assert!(matches!(game.next_round_report(), Err(GameError::Rule(_))));
+        assert!(matches!(game.run_round(&mut ChaCha8Rng::seed_from_u64(1)), Err(GameError::Rule(_))));
+        assert!(game.next_round.is_some() && game.prev_rounds.is_empty());
+
+        game.prev_rounds.push(Round::Boss(boss));
+        assert!(matches!(game.round_report(0), Err(GameError::Rule(_))));
+        assert_eq!(crate::csv::battles(&game).lines().count(), 1); // just the heading
+    }
+}
@@ -4,7 +4,7 @@ use strum_macros::*;
 use strum::IntoEnumIterator;
 use std::fmt;
 use std::str::FromStr;
-use rand::thread_rng;
+use rand::Rng;
 use rand::seq::SliceRandom;
 
 use crate::boss::BossRound;
@@ -12,8 +12,10 @@ use crate::batlog::Batlog;
 use crate::fighter::Fighter;
 
 
-#[derive(Serialize, Deserialize, Debug, EnumIter, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, EnumIter, IntoStaticStr, Clone, PartialEq)]
+#[strum(serialize_all = "lowercase")] // the names tab completion offers, see names()
 pub enum Arena {
+    #[strum(serialize = "amphitheater")]
     Ampitheater, // does nothing
     // this is spelled wrong SHUT UP
     Siphon, // no class effects
@@ -24,7 +26,8 @@ pub enum Arena {
     SoftPlayArea, // -1 from highest stat
 }
 
-#[derive(Serialize, Deserialize, Debug, EnumIter, Clone, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, EnumIter, IntoStaticStr, Clone, PartialEq)]
+#[strum(serialize_all = "lowercase")]
 pub enum Modifier {
     Rulebook, // does nothing
     TheCrowdDemandsBlood, // -1 to injury and bonus stat up on kill
@@ -37,6 +40,14 @@ pub enum Modifier {
     OlympicInspector,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Pairing { // how fighters get matched up when a round is made
+    #[default]
+    Random,
+    Rating, // neighbours by rating, like the olympic inspector does
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameRound {
     pub matchups: Vec<(usize, usize)>, // indexes into a list of fighters
@@ -44,6 +55,7 @@ pub struct GameRound {
     pub arena: Arena,
     pub modifier: Modifier,
     pub log: Batlog,
+    pub run: bool, // whether the round is in the past
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,29 +65,31 @@ pub enum Round {
 }
 
 impl GameRound {
-    pub fn new(fighters: &Vec<Fighter>, pre_matches: &mut Vec<(usize, usize)>, round_no: i32, arena: Option<Arena>, modifier: Option<Modifier>) -> GameRound {
+    #[allow(clippy::ptr_arg)]
+    pub fn new(fighters: &Vec<Fighter>, pre_matches: &mut Vec<(usize, usize)>, round_no: i32, arena: Option<Arena>, modifier: Option<Modifier>, pairing: Pairing, rng: &mut impl Rng) -> GameRound {
         let modifier = match modifier {
-            None => Modifier::iter().choose(&mut rand::thread_rng()).unwrap(),
+            None => Modifier::iter().choose(rng).unwrap(),
             Some(m) => m
         };
-        let (mut matchups, sitting_out) = match modifier {
-            Modifier::OlympicInspector => {
-                generate_olympics(fighters)
+        let (mut matchups, sitting_out) = match (&modifier, pairing) {
+            (Modifier::OlympicInspector, _) | (_, Pairing::Rating) => {
+                generate_olympics(fighters, rng)
             }
             _ => {
-                generate_matchups(fighters)
+                generate_matchups(fighters, rng)
             }
         };
         matchups.append(pre_matches);
 
         let arena = match arena {
-            None => Arena::iter().choose(&mut rand::thread_rng()).unwrap(),
+            None => Arena::iter().choose(rng).unwrap(),
             Some(a) => a
         };
 
         GameRound {
             matchups, sitting_out, arena, modifier,
-            log: Batlog::new(round_no)
+            log: Batlog::new(round_no),
+            run: false
         }
     }
 }
@@ -94,6 +108,23 @@ impl fmt::Display for Arena {
         })
     }
 }
+impl Arena {
+    pub fn names() -> Vec<&'static str> { // one that parses for each arena
+        Arena::iter().map(|a| a.into()).collect()
+    }
+
+    pub fn description(&self) -> &'static str { // same as the rules in the readme
+        match self {
+            Arena::Ampitheater => "does nothing",
+            Arena::Siphon => "no class effects",
+            Arena::ClimbingWall => "dominating on strength instantly wins you the match",
+            Arena::Hills => "double speed",
+            Arena::Library => "all stats higher than your skill stat are reduced to the level of your skill stat",
+            Arena::CrocPit => "a draw counts as a loss for both players, as you do not lose against the crocodiles",
+            Arena::SoftPlayArea => "-1 from your highest stat"
+        }
+    }
+}
 impl FromStr for Arena {
     type Err = String;
 
@@ -125,6 +156,22 @@ impl fmt::Display for Modifier {
     }
 }
 
+impl Modifier {
+    pub fn names() -> Vec<&'static str> {
+        Modifier::iter().map(|m| m.into()).collect()
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Modifier::Rulebook => "does nothing",
+            Modifier::TheCrowdDemandsBlood => "-1 to injury rolls, and a bonus stat up if your opponent dies",
+            Modifier::MedicalAssistance => "+1 to injury rolls",
+            Modifier::OhShitSheHasAGun => "10% chance for a fighter to be shot when the match starts. this can happen to both fighters",
+            Modifier::PumpkinSpiceEyeExams => "all stat ups earned will be spent on skill",
+            Modifier::OlympicInspector => "fighters are matched with another fighter with similar rating"
+        }
+    }
+}
 impl FromStr for Modifier {
     type Err = String;
 
@@ -141,7 +188,28 @@ impl FromStr for Modifier {
     }
 }
 
-fn generate_matchups(fighters: &[Fighter]) -> (Vec<(usize, usize)>, Option<usize>) {
+impl fmt::Display for Pairing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Pairing::Random => "random",
+            Pairing::Rating => "rating"
+        })
+    }
+}
+impl FromStr for Pairing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Pairing, String> {
+        match s.to_lowercase().as_str() {
+            "random" => Ok(Pairing::Random),
+            "rating" | "rated" => Ok(Pairing::Rating),
+            _ => Err(format!("pairing {} not recognised (expected random or rating)", s))
+        }
+    }
+}
+
+#[allow(clippy::needless_range_loop, clippy::manual_is_multiple_of)]
+fn generate_matchups(fighters: &[Fighter], rng: &mut impl Rng) -> (Vec<(usize, usize)>, Option<usize>) {
     let mut ret: Vec<(usize, usize)> = Vec::new();
     let mut living_fighters: Vec<usize> = Vec::new();
     
@@ -153,7 +221,7 @@ fn generate_matchups(fighters: &[Fighter]) -> (Vec<(usize, usize)>, Option<usize
 
     //println!("{:?}", living_fighters);
     
-    living_fighters.shuffle(&mut thread_rng()); // shuffle
+    living_fighters.shuffle(rng); // shuffle
     
     let sitting_out = if living_fighters.len() % 2 != 0 { // odd number of fighters
         living_fighters.pop() // this is easier than impling 3 ways
@@ -169,7 +237,8 @@ fn generate_matchups(fighters: &[Fighter]) -> (Vec<(usize, usize)>, Option<usize
     (ret, sitting_out)
 }
 
-fn generate_olympics(fighters: &[Fighter]) -> (Vec<(usize, usize)>, Option<usize>) {
+#[allow(clippy::needless_range_loop, clippy::manual_is_multiple_of)]
+fn generate_olympics(fighters: &[Fighter], rng: &mut impl Rng) -> (Vec<(usize, usize)>, Option<usize>) {
     let mut ret: Vec<(usize, usize)> = Vec::new();
     let mut living_fighters: Vec<usize> = Vec::new();
     
@@ -179,7 +248,7 @@ fn generate_olympics(fighters: &[Fighter]) -> (Vec<(usize, usize)>, Option<usize
         }
     }
     
-    living_fighters.shuffle(&mut thread_rng()); // shuffle
+    living_fighters.shuffle(rng); // shuffle
     
     let sitting_out = if living_fighters.len() % 2 != 0 { // odd number of fighters
         living_fighters.pop() // this is easier than impling 3 ways
@@ -194,4 +263,41 @@ fn generate_olympics(fighters: &[Fighter]) -> (Vec<(usize, usize)>, Option<usize
     }
 
     (ret, sitting_out)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighter::Class;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn test_rating_pairing() {
+        let mut fighters = Vec::new();
+        for rating in [50, 0, 40, 10, 30, 20] {
+            let mut f = Fighter::new(format!("r{}", rating), String::from("o"), Class::Mutant, 3, 3, 3);
+            f.rating = rating;
+            fighters.push(f)
+        }
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let r = GameRound::new(&fighters, &mut Vec::new(), 1, None, Some(Modifier::Rulebook), Pairing::Rating, &mut rng);
+        let mut pairs: Vec<(i32, i32)> = r.matchups.iter().map(|(a, b)| (fighters[*a].rating, fighters[*b].rating)).collect();
+        pairs.sort();
+        assert_eq!(pairs, vec![(0, 10), (20, 30), (40, 50)]);
+        assert_eq!("Rating".parse::<Pairing>(), Ok(Pairing::Rating));
+        assert!("best".parse::<Pairing>().is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Arena::names().len(), Arena::iter().count());
+        for (name, a) in Arena::names().iter().zip(Arena::iter()) {
+            assert_eq!(name.parse::<Arena>(), Ok(a))
+        }
+        for (name, m) in Modifier::names().iter().zip(Modifier::iter()) {
+            assert_eq!(name.parse::<Modifier>(), Ok(m))
+        }
+        assert_eq!(Arena::names()[0], "amphitheater");
+    }
+}
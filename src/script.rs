@@ -0,0 +1,55 @@
+use crate::utils::split_script_line;
+use crate::error::GameError;
+
+// scripts for run-script: one command per line, same as you'd type after `load NAME`. blank lines and # comments are skipped
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptLine {
+    pub line_no: usize, // counting from 1, for error messages
+    pub args: Vec<String>,
+}
+
+pub fn parse_script(text: &str) -> Result<Vec<ScriptLine>, GameError> { // checks the whole thing up front, so a typo on the last line doesn't leave half a script run
+    let mut ret = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let args = match split_script_line(line) {
+            Ok(a) => a,
+            Err(e) => return Err(GameError::Parse(format!("line {}: {}", i + 1, e)))
+        };
+        match args.first().map(|a| a.as_str()) {
+            None => continue,
+            Some("run-script") => return Err(GameError::Parse(format!("line {}: scripts can't run other scripts", i + 1))),
+            Some("undo") => return Err(GameError::Parse(format!("line {}: scripts can't undo, the whole script is one step to undo afterwards", i + 1))),
+            Some(_) => ret.push(ScriptLine { line_no: i + 1, args })
+        }
+    }
+    Ok(ret)
+}
+
+impl ScriptLine {
+    pub fn fail(&self, message: &str) -> String { // an error from running the line, with the line it came from
+        match message {
+            "" => format!("line {} ({}) failed. nothing was saved", self.line_no, self.args.join(" ")), // argparse already said why
+            m => format!("line {} ({}): {}. nothing was saved", self.line_no, self.args.join(" "), m)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let script = "# weekly routine\n\nadd-stats 0 1 0 0  # bob's spend\narrange-match 0 1\nedit-fighter 2 --note 'big # day'\n";
+        let lines = parse_script(script).unwrap();
+        assert_eq!(lines.iter().map(|l| l.line_no).collect::<Vec<usize>>(), vec![3, 4, 5]);
+        assert_eq!(lines[0].args, vec!["add-stats", "0", "1", "0", "0"]);
+        assert_eq!(lines[2].args[3], "big # day"); // quoted, so not a comment
+
+        assert!(matches!(parse_script("info\nrun-script x"), Err(GameError::Parse(_))));
+        assert!(matches!(parse_script("undo"), Err(GameError::Parse(_))));
+        assert_eq!(parse_script("info\nadd-fighter 'bob").unwrap_err(), GameError::Parse(String::from("line 2: unclosed quote")));
+        assert_eq!(lines[0].fail("fighter index 0 out of range"), "line 3 (add-stats 0 1 0 0): fighter index 0 out of range. nothing was saved");
+    }
+}
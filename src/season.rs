@@ -0,0 +1,161 @@
+use serde::{Serialize, Deserialize};
+
+use crate::game::GameState;
+use crate::fighter::Fighter;
+use crate::error::GameError;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SeasonLink { // where a fighter came from, so their career can be followed back
+    pub path: String,
+    pub season_name: String,
+    pub fighter: usize, // index in that save
+}
+
+#[derive(Debug, Clone)]
+pub struct CarryOver { // what survives from one season to the next
+    pub keep_stats: i32, // percentage of each stat kept, rounded down
+    pub keep_rating: bool,
+    pub kill_bonus: i32, // unspent points per kill
+}
+impl Default for CarryOver {
+    fn default() -> Self {
+        CarryOver {
+            keep_stats: 100,
+            keep_rating: false,
+            kill_bonus: 0
+        }
+    }
+}
+
+impl CarryOver {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0..=100).contains(&self.keep_stats) {
+            return Err(format!("stat percentage must be between 0 and 100 (found {})", self.keep_stats))
+        }
+        if self.kill_bonus < 0 {
+            return Err(format!("kill bonus cannot be negative (found {})", self.kill_bonus))
+        }
+        Ok(())
+    }
+
+    pub fn carry(&self, f: &Fighter, link: SeasonLink) -> Fighter {
+        let keep = |stat: i32| stat * self.keep_stats / 100;
+        let mut new = Fighter::new(f.name.clone(), f.owner.clone(), f.class.clone(), keep(f.strength), keep(f.speed), keep(f.skill));
+        if self.keep_rating {
+            new.rating = f.rating
+        }
+        new.unspent_points = f.unspent_points + f.kills * self.kill_bonus;
+        new.previous = Some(link);
+        new
+    }
+}
+
+pub fn new_season(old: &GameState, old_path: &str, season_name: &str, rule: &CarryOver) -> Result<GameState, String> { // the survivors of old, ready for a new season
+    rule.validate()?;
+    if old.next_round.is_some() {
+        return Err(format!("{} still has a round scheduled, run or cancel it first", old.season_name))
+    }
+    let mut game = GameState::new_game(season_name);
+    for (i, f) in old.fighters.iter().enumerate() {
+        if f.dead {
+            continue
+        }
+        game.fighters.push(rule.carry(f, SeasonLink {
+            path: old_path.to_string(),
+            season_name: old.season_name.clone(),
+            fighter: i
+        }))
+    }
+    Ok(game)
+}
+
+pub fn lineage(game: &GameState, index: usize) -> Result<Vec<(String, usize, Fighter)>, GameError> { // follows a fighter back through the seasons they were carried over from, newest first
+    let mut f = match game.fighters.get(index) {
+        Some(f) => f.clone(),
+        None => return Err(GameError::FighterOutOfRange(index))
+    };
+    let mut seasons = vec![(game.season_name.clone(), index, f.clone())];
+    let mut visited: Vec<(String, usize)> = Vec::new();
+    while let Some(link) = f.previous {
+        if visited.contains(&(link.path.clone(), link.fighter)) { // hand edited or copied saves can point back at each other
+            return Err(GameError::Parse(format!("fighter {} of {} links back to a season they were already in, the links go round in circles", link.fighter, link.path)))
+        }
+        visited.push((link.path.clone(), link.fighter));
+        f = match GameState::peek_file(&link.path) {
+            Ok(g) => match g.fighters.get(link.fighter) {
+                Some(f) => f.clone(),
+                None => return Err(GameError::NotFound(format!("{} has no fighter {}, the link is broken", link.path, link.fighter)))
+            }
+            Err(GameError::NotFound(_)) => return Err(GameError::NotFound(format!("{} ({}) is missing, the link is broken", link.season_name, link.path))),
+            Err(e) => return Err(e)
+        };
+        seasons.push((link.season_name, link.fighter, f.clone()));
+    }
+    Ok(seasons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighter::Class;
+
+    #[test]
+    fn test_new_season() {
+        let mut old = GameState::new_game("one");
+        let mut a = Fighter::new(String::from("a"), String::from("al"), Class::Tank, 5, 4, 3);
+        a.rating = 7;
+        a.kills = 2;
+        a.battles_won = 3;
+        a.battles_fought = 4;
+        a.unspent_points = 1;
+        let mut b = Fighter::new(String::from("b"), String::from("bo"), Class::Dom, 3, 3, 3);
+        b.dead = true;
+        old.fighters = vec![b, a];
+
+        let rule = CarryOver { keep_stats: 50, keep_rating: false, kill_bonus: 2 };
+        let new = new_season(&old, "/tmp/one.json", "two", &rule).unwrap();
+        assert_eq!(new.fighters.len(), 1);
+        let f = &new.fighters[0];
+        assert_eq!((f.strength, f.speed, f.skill), (2, 2, 1));
+        assert_eq!((f.rating, f.kills, f.battles_won, f.battles_fought), (0, 0, 0, 0));
+        assert_eq!(f.unspent_points, 5);
+        assert_eq!(f.previous, Some(SeasonLink { path: String::from("/tmp/one.json"), season_name: String::from("one"), fighter: 1 }));
+
+        assert!(new_season(&old, "", "two", &CarryOver { keep_stats: 150, ..CarryOver::default() }).is_err());
+    }
+
+    #[test]
+    fn test_broken_links() {
+        let path = std::env::temp_dir().join(format!("gw_season_test_{}.json", std::process::id())).to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(&path);
+        let mut game = GameState::new_game("two");
+        let mut f = Fighter::new(String::from("a"), String::from("al"), Class::Tank, 5, 4, 3);
+        f.previous = Some(SeasonLink { path: path.clone(), season_name: String::from("one"), fighter: 3 });
+        game.fighters.push(f);
+        assert!(matches!(lineage(&game, 0), Err(GameError::NotFound(_)))); // save is gone
+
+        std::fs::write(&path, "{ not json").unwrap();
+        let junk = lineage(&game, 0);
+        GameState::new_game("one").export(&path).unwrap();
+        let no_fighter = lineage(&game, 0);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(junk, Err(GameError::Parse(_))));
+        assert!(matches!(no_fighter, Err(GameError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_lineage_loop() {
+        let path = std::env::temp_dir().join(format!("gw_season_loop_{}.json", std::process::id())).to_str().unwrap().to_string();
+        let mut old = GameState::new_game("one");
+        let mut f = Fighter::new(String::from("a"), String::from("al"), Class::Tank, 5, 4, 3);
+        f.previous = Some(SeasonLink { path: path.clone(), season_name: String::from("one"), fighter: 0 }); // carried over from itself
+        old.fighters.push(f.clone());
+        old.export(&path).unwrap();
+
+        let mut game = GameState::new_game("two");
+        game.fighters.push(f);
+        let l = lineage(&game, 0);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(l, Err(GameError::Parse(_))));
+    }
+}
@@ -0,0 +1,155 @@
+use rustyline::{Editor, Context, Helper, Hinter, Highlighter, Validator};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+
+use gladiator_war::game::GameState;
+use gladiator_war::round::{Arena, Modifier};
+use gladiator_war::utils::{split_args, timestamp};
+use gladiator_war::lock::SaveLock;
+use crate::cli::GwError;
+use crate::cli::ProgramOptions;
+
+const COMMANDS: &[&str] = &[
+    "info", "add-fighter", "import-fighters", "list-fighters", "show-fighter", "lineage", "next-round", "show-round", "log-round", "add-stats", "edit-fighter", "list-edits",
+    "arrange-match", "run-round", "new-round", "cancel-round", "undo", "history", "check", "export", "journal", "run-script", "publish",
+    "help", "save", "exit"
+];
+const FIGHTER_COMMANDS: &[&str] = &["add-stats", "edit-fighter", "arrange-match", "lineage", "show-fighter"]; // the ones that take fighter indexes
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ShellHelper {
+    fighters: Vec<String>,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let word = line[start..pos].to_lowercase();
+        let before: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let names = |list: &[&str]| list.iter()
+            .filter(|n| n.starts_with(&word))
+            .map(|n| Pair { display: n.to_string(), replacement: format!("{} ", n) })
+            .collect::<Vec<Pair>>();
+
+        let candidates = match (before.first(), before.last()) {
+            (None, _) => names(COMMANDS),
+            (_, Some(&"-a")) => names(&Arena::names()),
+            (_, Some(&"-m")) => names(&Modifier::names()),
+            (_, Some(&"-p")) => names(&["random", "rating"]),
+            (Some(c), _) if FIGHTER_COMMANDS.contains(c) && !word.starts_with('-') => { // names complete to indexes, since that's what the commands want
+                self.fighters.iter().enumerate()
+                    .filter(|(i, n)| n.to_lowercase().starts_with(&word) || i.to_string().starts_with(&word))
+                    .map(|(i, n)| Pair { display: format!("{} [{}]", n, i), replacement: format!("{} ", i) })
+                    .collect()
+            }
+            _ => Vec::new()
+        };
+        Ok((start, candidates))
+    }
+}
+
+fn print_help() {
+    println!("any command that works after `load SAVE` works here, without the `load SAVE` bit");
+    println!("commands: {}", COMMANDS.join(", "));
+    println!("changes are saved after every command. tab completes commands, fighter names (into their indexes), arenas after -a, modifiers after -m and pairings after -p");
+}
+
+pub fn run_shell(po: &mut ProgramOptions, save_index: usize, history_path: &str) -> Result<(), GwError> { // keeps the game loaded and locked between commands
+    let entry = po.global_data.saves[save_index].clone();
+    let _lock = SaveLock::acquire(&entry.path)?;
+    let mut game = GameState::load_from_file(&entry.path).map_err(GwError::from)?;
+
+    let mut rl: Editor<ShellHelper, DefaultHistory> = match Editor::new() {
+        Ok(r) => r,
+        Err(e) => return Err(GwError::Io(format!("could not start the shell: {}", e)))
+    };
+    rl.set_helper(Some(ShellHelper { fighters: game.fighters.iter().map(|f| f.name.clone()).collect() }));
+    let _ = rl.load_history(history_path); // no history yet is fine
+
+    if po.verbosity > -1 {
+        println!("loaded {} ({}). type help for help, exit to leave", game.season_name, entry.name);
+        if entry.archived {
+            println!("this save is archived, so it can only be looked at")
+        }
+    }
+    let prompt = format!("{}> ", entry.name);
+    let result = loop {
+        let line = match rl.readline(&prompt) {
+            Ok(l) => l,
+            Err(ReadlineError::Interrupted) => continue, // ctrl-c just clears the line
+            Err(ReadlineError::Eof) => break Ok(()),
+            Err(e) => break Err(GwError::Io(format!("could not read input: {}", e)))
+        };
+        if line.trim().is_empty() {
+            continue
+        }
+        let _ = rl.add_history_entry(line.as_str());
+        let mut args = match split_args(&line) {
+            Ok(a) => a,
+            Err(e) => {
+                println!("error: {}", e);
+                continue
+            }
+        };
+        match args[0].as_str() {
+            "exit" | "quit" => break Ok(()),
+            "help" => {
+                print_help();
+                continue
+            }
+            "save" => {} // saving happens below anyway
+            _ => {
+                args.insert(0, entry.name.clone()); // argparse wants something in args[0]
+                let before = game.clone(); // errors eat the game
+                game = match crate::do_things_to_existing_game(args, game, po, entry.archived) {
+                    Ok((g, _)) => g,
+                    Err(e) => {
+                        if !e.message().is_empty() {
+                            println!("error: {}", e)
+                        }
+                        before
+                    }
+                };
+            }
+        }
+
+        let before = (game.generation, game.journal_len);
+        if let Err(e) = game.save_over(&entry.path) { // most likely someone else wrote to the file, so carrying on would just lose more work
+            break Err(e.into())
+        }
+        if (game.generation, game.journal_len) != before {
+            po.global_data.saves[save_index].last_modified = timestamp()
+        }
+        if let Some(h) = rl.helper_mut() {
+            h.fighters = game.fighters.iter().map(|f| f.name.clone()).collect()
+        }
+    };
+
+    let _ = rl.save_history(history_path);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        let h = ShellHelper { fighters: vec![String::from("bill"), String::from("Bob"), String::from("c")] };
+        let history = DefaultHistory::new();
+        let ctx = Context::new(&history);
+        let complete = |line: &str| {
+            let (start, c) = h.complete(line, line.len(), &ctx).unwrap();
+            (start, c.into_iter().map(|p| p.replacement).collect::<Vec<String>>())
+        };
+        assert_eq!(complete("list-f"), (0, vec![String::from("list-fighters ")]));
+        assert_eq!(complete("add-stats b"), (10, vec![String::from("0 "), String::from("1 ")]));
+        assert_eq!(complete("new-round -a cr"), (13, vec![String::from("crocpit ")]));
+        assert_eq!(complete("new-round -m bl").1, Vec::<String>::new()); // modifiers match on full names
+        assert_eq!(complete("info x").1, Vec::<String>::new());
+    }
+}
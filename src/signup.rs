@@ -0,0 +1,214 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::fighter::{Fighter, Class};
+use crate::csv;
+
+// signup sheets for import-fighters, as csv (what forms and spreadsheets export) or a json list of objects.
+// columns are matched to fighter fields by their heading, so extra columns (timestamp, email...) are ignored and the order doesn't matter
+
+pub const FIELDS: [&str; 6] = ["name", "owner", "class", "strength", "speed", "skill"];
+
+type Sheet = (Vec<String>, Vec<(usize, Vec<String>)>); // the headings, then each row with its number
+
+fn normalise(heading: &str) -> String { // "Fighter Name:" and "fighter_name" are the same column
+    heading.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+fn guess_field(heading: &str) -> Option<&'static str> {
+    Some(match normalise(heading).as_str() {
+        "name" | "fighter" | "fightername" | "gladiator" | "gladiatorname" => "name",
+        "owner" | "player" | "playername" | "user" | "username" | "discord" => "owner",
+        "class" | "fighterclass" => "class",
+        "strength" | "str" => "strength",
+        "speed" | "spd" => "speed",
+        "skill" | "skl" => "skill",
+        _ => return None
+    })
+}
+
+pub fn parse_map(args: &[String]) -> Result<Vec<(&'static str, String)>, String> { // --map FIELD=HEADING, for headings that can't be guessed
+    let mut ret = Vec::new();
+    for a in args {
+        let (field, heading) = match a.split_once('=') {
+            Some((f, h)) if !h.trim().is_empty() => (f.trim().to_lowercase(), h.trim().to_string()),
+            _ => return Err(format!("--map {} should look like FIELD=HEADING", a))
+        };
+        match FIELDS.iter().find(|f| **f == field) {
+            Some(f) => ret.push((*f, heading)),
+            None => return Err(format!("--map: {} isn't a fighter field (expected one of {})", field, FIELDS.join(", ")))
+        }
+    }
+    Ok(ret)
+}
+
+fn columns(headings: &[String], map: &[(&'static str, String)]) -> Result<Vec<Option<&'static str>>, Vec<String>> { // which field each column holds, if any
+    let fields: Vec<Option<&'static str>> = headings.iter().map(|h| {
+        match map.iter().find(|(_, m)| normalise(m) == normalise(h)) {
+            Some((f, _)) => Some(*f),
+            None => guess_field(h).filter(|g| !map.iter().any(|(f, _)| f == g)) // a mapped field ignores the column it would have guessed
+        }
+    }).collect();
+
+    let mut problems = Vec::new();
+    for field in FIELDS {
+        let found: Vec<&String> = headings.iter().zip(&fields).filter(|(_, f)| **f == Some(field)).map(|(h, _)| h).collect();
+        match found.len() {
+            0 => problems.push(format!("no column for {} (use --map {}=HEADING)", field, field)),
+            1 => {}
+            _ => problems.push(format!("columns {} all look like {} (use --map {}=HEADING to pick one)",
+                found.iter().map(|h| format!("\"{}\"", h)).collect::<Vec<String>>().join(", "), field, field))
+        }
+    }
+    for (_, heading) in map {
+        if !headings.iter().any(|h| normalise(h) == normalise(heading)) {
+            problems.push(format!("--map: there's no column called \"{}\"", heading))
+        }
+    }
+    if problems.is_empty() { Ok(fields) } else { Err(problems) }
+}
+
+fn csv_rows(text: &str) -> Result<Sheet, Vec<String>> { // the headings, then each row with its row number in the sheet
+    let mut rows = csv::parse(text).map_err(|e| vec![e])?.into_iter();
+    let headings = match rows.next() {
+        Some(h) => h,
+        None => return Err(vec![String::from("the file is empty")])
+    };
+    Ok((headings, rows.enumerate().map(|(i, r)| (i + 2, r)).collect()))
+}
+
+fn json_rows(text: &str) -> Result<Sheet, Vec<String>> { // same shape as csv, with every key that turns up as a heading
+    let entries = match serde_json::from_str::<Value>(text) {
+        Ok(Value::Array(a)) => a,
+        Ok(_) => return Err(vec![String::from("expected a list of fighters")]),
+        Err(e) => return Err(vec![format!("json failed to parse: {}", e)])
+    };
+    let mut headings: Vec<String> = Vec::new();
+    let mut problems = Vec::new();
+    for (i, e) in entries.iter().enumerate() {
+        match e.as_object() {
+            Some(o) => headings.extend(o.keys().filter(|k| !headings.contains(k)).cloned().collect::<Vec<String>>()),
+            None => problems.push(format!("entry {}: expected an object", i + 1))
+        }
+    }
+    let rows = entries.iter().enumerate().filter_map(|(i, e)| {
+        let o = e.as_object()?;
+        Some((i + 1, headings.iter().map(|h| match o.get(h) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(v) => v.to_string()
+        }).collect()))
+    }).collect();
+    if problems.is_empty() { Ok((headings, rows)) } else { Err(problems) }
+}
+
+fn fighter_from(cells: &HashMap<&str, &str>) -> Result<Fighter, Vec<String>> { // every problem with the row, not just the first
+    let mut problems = Vec::new();
+    let (name, owner) = (cells["name"].trim(), cells["owner"].trim());
+    if name.is_empty() {
+        problems.push(String::from("no name"))
+    }
+    if owner.is_empty() {
+        problems.push(String::from("no owner"))
+    }
+    let class = match cells["class"].trim().parse::<Class>() {
+        Ok(c) => Some(c),
+        Err(e) => {
+            problems.push(e);
+            None
+        }
+    };
+    let mut stats = [0; 3];
+    for (i, stat) in ["strength", "speed", "skill"].iter().enumerate() {
+        match cells[stat].trim().parse::<i32>() {
+            Ok(v) if v < 0 => problems.push(format!("{} cannot be negative (found {})", stat, v)),
+            Ok(v) => stats[i] = v,
+            Err(_) => problems.push(format!("{} value \"{}\" failed to parse!", stat, cells[stat].trim()))
+        }
+    }
+    let spent: i32 = stats.iter().sum();
+    if let Some(c) = &class {
+        if spent > c.starting_points() {
+            problems.push(format!("stats add up to {}, but a {} only starts with {} points", spent, c, c.starting_points()))
+        }
+    }
+    match class {
+        Some(c) if problems.is_empty() => {
+            let mut f = Fighter::new(name.to_string(), owner.to_string(), c.clone(), stats[0], stats[1], stats[2]);
+            f.unspent_points = c.starting_points() - spent; // anything they didn't spend on signup
+            Ok(f)
+        }
+        _ => Err(problems)
+    }
+}
+
+pub fn read_signups(text: &str, json: bool, map: &[(&'static str, String)], taken: &[String]) -> Result<Vec<Fighter>, Vec<String>> { // all the fighters, or every problem found
+    let (headings, rows) = if json { json_rows(text)? } else { csv_rows(text)? };
+    let fields = columns(&headings, map)?;
+    let what = if json { "entry" } else { "row" };
+
+    let mut fighters = Vec::new();
+    let mut problems = Vec::new();
+    let mut seen: HashMap<String, String> = taken.iter().enumerate().map(|(i, n)| (n.trim().to_lowercase(), format!("fighter {}", i))).collect();
+    for (row_no, row) in rows {
+        if row.iter().all(|c| c.trim().is_empty()) { // blank lines at the bottom of a sheet
+            continue
+        }
+        let cells: HashMap<&str, &str> = fields.iter().zip(&row).filter_map(|(f, c)| Some(((*f)?, c.as_str()))).collect();
+        let cells: HashMap<&str, &str> = FIELDS.iter().map(|f| (*f, cells.get(f).copied().unwrap_or(""))).collect(); // short rows just have empty cells
+        let label = match cells["name"].trim() {
+            "" => format!("{} {}", what, row_no),
+            n => format!("{} {} ({})", what, row_no, n)
+        };
+        let key = cells["name"].trim().to_lowercase();
+        if let Some(other) = seen.get(&key).filter(|_| !key.is_empty()) {
+            problems.push(format!("{}: the name is already taken by {}", label, other))
+        }
+        if !key.is_empty() {
+            seen.entry(key).or_insert(format!("{} {}", what, row_no));
+        }
+        match fighter_from(&cells) {
+            Ok(f) => fighters.push(f),
+            Err(p) => problems.extend(p.into_iter().map(|p| format!("{}: {}", label, p)))
+        }
+    }
+    if fighters.is_empty() && problems.is_empty() {
+        problems.push(format!("no fighters in the {}", if json { "list" } else { "sheet" }))
+    }
+    if problems.is_empty() { Ok(fighters) } else { Err(problems) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signups() {
+        let sheet = "Timestamp,Fighter Name,Discord,Class,STR,SPD,SKL\r\n1/2,\"big, bob\",al,chicken,4,4,4\r\n1/2,cy,dee,senator,5,5,2\r\n,,,,,,\r\n";
+        let fighters = read_signups(sheet, false, &[], &[]).unwrap();
+        assert_eq!(fighters.len(), 2);
+        assert_eq!((fighters[0].name.as_str(), fighters[0].owner.as_str(), &fighters[0].class), ("big, bob", "al", &Class::Tank));
+        assert_eq!((fighters[1].total(), fighters[1].unspent_points), (13, 1)); // the senator's spare point is kept
+
+        let bad = "name,owner,class,strength,speed,skill\nbob,al,wizard,4,4,4\nal,,mutant,9,9,x\nBob,cy,mutant,1,1,1\n";
+        let problems = read_signups(bad, false, &[], &[String::from("al")]).unwrap_err();
+        assert_eq!(problems, vec![
+            "row 2 (bob): class wizard failed to parse!",
+            "row 3 (al): the name is already taken by fighter 0",
+            "row 3 (al): no owner",
+            "row 3 (al): skill value \"x\" failed to parse!",
+            "row 3 (al): stats add up to 18, but a mutant only starts with 12 points",
+            "row 4 (Bob): the name is already taken by row 2"
+        ]);
+
+        let json = r#"[{"Gladiator": "ed", "Player Name": "fi", "class": "cleric", "strength": 3, "speed": "3", "skill": 3, "name": "fi's real name"}]"#;
+        assert!(read_signups(json, true, &[], &[]).unwrap_err()[0].starts_with("columns "));
+        let map = parse_map(&[String::from("name=gladiator")]).unwrap();
+        let fighters = read_signups(json, true, &map, &[]).unwrap();
+        assert_eq!((fighters[0].name.as_str(), fighters[0].unspent_points), ("ed", 3));
+        assert!(parse_map(&[String::from("hat=x")]).is_err());
+        let map = parse_map(&[String::from("owner = What's your discord?")]).unwrap();
+        assert_eq!(read_signups("fighter,What's your discord?,class,str,spd,skl\nal,bo,swarm,1,2,3", false, &map, &[]).unwrap()[0].owner, "bo");
+        assert_eq!(read_signups("name,owner\n", false, &[], &[]).unwrap_err().len(), 4); // class and the three stats
+    }
+}
@@ -0,0 +1,361 @@
+use serde_json::{Value, Map, json};
+use rusqlite::{Connection, params, params_from_iter};
+use rusqlite::types::Value as SqlValue;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::utils::{get_last, write_atomic};
+use crate::error::GameError;
+
+pub trait Storage { // where a save game lives. works on the json form of the game so migrations work the same for every backend
+    fn read(&self, filename: &str) -> Result<Value, GameError>; // as stored, before any migrations
+    fn write(&self, filename: &str, game: &Value) -> Result<(), GameError>; // must replace the whole save in one go or not at all
+}
+
+pub fn storage_for(filename: &str) -> Box<dyn Storage> { // sniff existing files, go by extension for new ones
+    let mut magic = [0u8; 16];
+    let is_sqlite = match File::open(filename).and_then(|mut f| f.read_exact(&mut magic)) {
+        Ok(_) => &magic == b"SQLite format 3\0",
+        Err(_) => {
+            let (_, ext) = get_last(filename, '.');
+            matches!(ext, "db" | "sqlite" | "sqlite3")
+        }
+    };
+    if is_sqlite {
+        Box::new(SqliteStorage)
+    }
+    else {
+        Box::new(JsonStorage)
+    }
+}
+
+pub struct JsonStorage;
+
+impl Storage for JsonStorage {
+    fn read(&self, filename: &str) -> Result<Value, GameError> {
+        match fs::read_to_string(filename) {
+            Ok(s) => match serde_json::from_str::<Value>(&s) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(GameError::Parse(format!("json parse error for file {} ({})", filename, e)))
+            }
+            Err(_) => Err(GameError::Io(format!("file read error for file {}", filename))) // exit without panicking
+        }
+    }
+
+    fn write(&self, filename: &str, game: &Value) -> Result<(), GameError> {
+        write_atomic(filename, serde_json::to_string_pretty(game).unwrap()).map_err(GameError::Io)
+    }
+}
+
+pub struct SqliteStorage;
+
+#[derive(Clone, Copy)]
+enum Col { // how a json value is stored in a column
+    Int,
+    Bool,
+    Text,
+    Json, // anything else, stored as json text
+}
+
+// known fields get real columns so they can be queried. anything else on the object goes in the "extra" column as json
+const FIGHTER_COLS: [(&str, Col); 13] = [
+    ("name", Col::Text), ("owner", Col::Text), ("class", Col::Text), ("strength", Col::Int), ("speed", Col::Int), ("skill", Col::Int),
+    ("dead", Col::Bool), ("rating", Col::Int), ("kills", Col::Int), ("battles_won", Col::Int), ("battles_fought", Col::Int),
+    ("unspent_points", Col::Int), ("pre_matched", Col::Bool)
+];
+const ROUND_COLS: [(&str, Col); 5] = [("arena", Col::Text), ("modifier", Col::Text), ("sitting_out", Col::Int), ("run", Col::Bool), ("matchups", Col::Json)];
+const BATTLE_COLS: [(&str, Col); 8] = [
+    ("fighter_1", Col::Int), ("rolls_1", Col::Json), ("injury_1", Col::Int), ("fighter_2", Col::Int), ("rolls_2", Col::Json), ("injury_2", Col::Int),
+    ("points", Col::Int), ("result", Col::Text)
+];
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+CREATE TABLE IF NOT EXISTS fighters (id INTEGER PRIMARY KEY, name TEXT, owner TEXT, class TEXT, strength INTEGER, speed INTEGER, skill INTEGER,
+    dead INTEGER, rating INTEGER, kills INTEGER, battles_won INTEGER, battles_fought INTEGER, unspent_points INTEGER, pre_matched INTEGER, extra TEXT);
+CREATE TABLE IF NOT EXISTS rounds (id INTEGER PRIMARY KEY, kind TEXT, round_no INTEGER, arena TEXT, modifier TEXT, sitting_out INTEGER, run INTEGER,
+    matchups TEXT, extra TEXT);
+CREATE TABLE IF NOT EXISTS battles (round_id INTEGER, battle_no INTEGER, fighter_1 INTEGER, rolls_1 TEXT, injury_1 INTEGER, fighter_2 INTEGER,
+    rolls_2 TEXT, injury_2 INTEGER, points INTEGER, result TEXT, extra TEXT, PRIMARY KEY (round_id, battle_no));
+CREATE TABLE IF NOT EXISTS events (round_id INTEGER, battle_no INTEGER, event_no INTEGER, text TEXT, PRIMARY KEY (round_id, battle_no, event_no));
+";
+
+fn to_sql(v: &Value, col: Col) -> SqlValue {
+    match (v, col) {
+        (Value::Null, _) => SqlValue::Null,
+        (Value::Bool(b), Col::Bool) => SqlValue::Integer(*b as i64),
+        (Value::Number(n), Col::Int) if n.is_i64() => SqlValue::Integer(n.as_i64().unwrap()),
+        (Value::String(s), Col::Text) => SqlValue::Text(s.clone()),
+        _ => SqlValue::Text(v.to_string()) // also catches values that dont match their column, so nothing gets lost
+    }
+}
+
+fn from_sql(v: SqlValue, col: Col) -> Value {
+    match (v, col) {
+        (SqlValue::Null, _) => Value::Null,
+        (SqlValue::Integer(i), Col::Bool) => json!(i != 0),
+        (SqlValue::Integer(i), _) => json!(i),
+        (SqlValue::Real(r), _) => json!(r),
+        (SqlValue::Text(s), Col::Text) => Value::String(s),
+        (SqlValue::Text(s), _) => serde_json::from_str(&s).unwrap_or(Value::String(s)),
+        (SqlValue::Blob(_), _) => Value::Null
+    }
+}
+
+fn split_cols(obj: &Value, cols: &[(&str, Col)]) -> (Vec<SqlValue>, String) { // (column values, json of everything else)
+    let mut extra = obj.as_object().cloned().unwrap_or_default();
+    let vals = cols.iter().map(|(name, col)| to_sql(&extra.remove(*name).unwrap_or(Value::Null), *col)).collect();
+    (vals, Value::Object(extra).to_string())
+}
+
+fn join_cols(row: &rusqlite::Row, first: usize, cols: &[(&str, Col)]) -> rusqlite::Result<Map<String, Value>> { // reverse of split_cols. extra is the column after the known ones
+    let mut obj = Map::new();
+    for (i, (name, col)) in cols.iter().enumerate() {
+        obj.insert(name.to_string(), from_sql(row.get(first + i)?, *col));
+    }
+    let extra: Option<String> = row.get(first + cols.len())?;
+    if let Some(Value::Object(e)) = extra.and_then(|e| serde_json::from_str(&e).ok()) {
+        obj.extend(e)
+    }
+    Ok(obj)
+}
+
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+type Rows = Vec<Vec<SqlValue>>; // each row's key columns come first
+
+fn sync_table(tx: &rusqlite::Transaction, table: &str, cols: &[&str], keys: usize, rows: Rows) -> rusqlite::Result<()> { // only touches rows that changed, so a save after one edit doesn't rewrite the whole league
+    let col_list = cols.join(", ");
+    let mut stored: HashMap<String, Vec<SqlValue>> = HashMap::new();
+    {
+        let mut stmt = tx.prepare(&format!("SELECT {} FROM {}", col_list, table))?;
+        let mut q = stmt.query([])?;
+        while let Some(r) = q.next()? {
+            let row = (0..cols.len()).map(|i| r.get::<_, SqlValue>(i)).collect::<rusqlite::Result<Vec<SqlValue>>>()?;
+            stored.insert(format!("{:?}", &row[..keys]), row);
+        }
+    }
+
+    let mut upsert = tx.prepare(&format!("INSERT OR REPLACE INTO {} ({}) VALUES ({})", table, col_list, placeholders(cols.len())))?;
+    for row in rows {
+        match stored.remove(&format!("{:?}", &row[..keys])) {
+            Some(old) if old == row => {} // unchanged
+            _ => { upsert.execute(params_from_iter(row))?; }
+        }
+    }
+
+    let key_match = cols[..keys].iter().map(|c| format!("{} = ?", c)).collect::<Vec<String>>().join(" AND ");
+    let mut delete = tx.prepare(&format!("DELETE FROM {} WHERE {}", table, key_match))?;
+    for (_, old) in stored { // whatever's left is gone from the game
+        delete.execute(params_from_iter(old.into_iter().take(keys)))?;
+    }
+    Ok(())
+}
+
+impl SqliteStorage {
+    fn write_inner(conn: &mut Connection, game: &Value) -> rusqlite::Result<()> {
+        let tx = conn.transaction()?; // everything or nothing
+        tx.execute_batch(SCHEMA)?;
+
+        let empty = Vec::new();
+        let mut meta = game.as_object().cloned().unwrap_or_default();
+        let fighters = meta.remove("fighters");
+        let rounds = meta.remove("prev_rounds");
+        let meta_rows: Rows = meta.iter().map(|(k, v)| vec![SqlValue::Text(k.clone()), SqlValue::Text(v.to_string())]).collect();
+
+        let mut fighter_rows: Rows = Vec::new();
+        for (i, f) in fighters.as_ref().and_then(|f| f.as_array()).unwrap_or(&empty).iter().enumerate() {
+            let (mut vals, extra) = split_cols(f, &FIGHTER_COLS);
+            vals.insert(0, SqlValue::Integer(i as i64));
+            vals.push(SqlValue::Text(extra));
+            fighter_rows.push(vals);
+        }
+
+        let (mut round_rows, mut battle_rows, mut event_rows): (Rows, Rows, Rows) = (Vec::new(), Vec::new(), Vec::new());
+        for (ri, round) in rounds.as_ref().and_then(|r| r.as_array()).unwrap_or(&empty).iter().enumerate() {
+            let (kind, mut r) = match round.as_object().and_then(|o| o.iter().next()) { // externally tagged enum, {"Standard": {...}}
+                Some((k, v)) => (k.clone(), v.clone()),
+                None => (String::new(), round.clone())
+            };
+            let mut fights = Value::Null;
+            let mut round_no = Value::Null;
+            if let Some(log) = r.get_mut("log").and_then(|l| l.as_object_mut()) { // battles get their own table
+                fights = log.remove("fights").unwrap_or(Value::Null);
+                round_no = log.remove("round_no").unwrap_or(Value::Null);
+            }
+            let (mut vals, extra) = split_cols(&r, &ROUND_COLS);
+            vals.splice(0..0, [SqlValue::Integer(ri as i64), SqlValue::Text(kind), to_sql(&round_no, Col::Int)]);
+            vals.push(SqlValue::Text(extra));
+            round_rows.push(vals);
+
+            for (bi, b) in fights.as_array().unwrap_or(&empty).iter().enumerate() {
+                let mut b = b.clone();
+                let events = b.as_object_mut().and_then(|o| o.remove("other_events")).unwrap_or(Value::Null);
+                let (mut vals, extra) = split_cols(&b, &BATTLE_COLS);
+                vals.splice(0..0, [SqlValue::Integer(ri as i64), SqlValue::Integer(bi as i64)]);
+                vals.push(SqlValue::Text(extra));
+                battle_rows.push(vals);
+
+                for (ei, e) in events.as_array().unwrap_or(&empty).iter().enumerate() {
+                    let text = e.as_str().map(String::from).unwrap_or(e.to_string());
+                    event_rows.push(vec![SqlValue::Integer(ri as i64), SqlValue::Integer(bi as i64), SqlValue::Integer(ei as i64), SqlValue::Text(text)]);
+                }
+            }
+        }
+
+        let with_extra = |first: &[&'static str], cols: &[(&'static str, Col)]| -> Vec<&'static str> {
+            first.iter().copied().chain(cols.iter().map(|c| c.0)).chain(["extra"]).collect()
+        };
+        sync_table(&tx, "meta", &["key", "value"], 1, meta_rows)?;
+        sync_table(&tx, "fighters", &with_extra(&["id"], &FIGHTER_COLS), 1, fighter_rows)?;
+        sync_table(&tx, "rounds", &with_extra(&["id", "kind", "round_no"], &ROUND_COLS), 1, round_rows)?;
+        sync_table(&tx, "battles", &with_extra(&["round_id", "battle_no"], &BATTLE_COLS), 2, battle_rows)?;
+        sync_table(&tx, "events", &["round_id", "battle_no", "event_no", "text"], 3, event_rows)?;
+        tx.commit()
+    }
+
+    fn read_inner(conn: &Connection) -> rusqlite::Result<Value> {
+        let mut game = Map::new();
+        {
+            let mut stmt = conn.prepare("SELECT key, value FROM meta")?;
+            let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?;
+            for row in rows {
+                let (k, v) = row?;
+                game.insert(k, serde_json::from_str(&v).unwrap_or(Value::Null));
+            }
+        }
+
+        let sql = format!("SELECT {}, extra FROM fighters ORDER BY id", FIGHTER_COLS.map(|c| c.0).join(", "));
+        let mut stmt = conn.prepare(&sql)?;
+        let fighters = stmt.query_map([], |r| join_cols(r, 0, &FIGHTER_COLS))?
+            .map(|f| f.map(Value::Object))
+            .collect::<rusqlite::Result<Vec<Value>>>()?;
+        game.insert(String::from("fighters"), Value::Array(fighters));
+
+        let mut events_stmt = conn.prepare("SELECT text FROM events WHERE round_id = ?1 AND battle_no = ?2 ORDER BY event_no")?;
+        let battle_sql = format!("SELECT battle_no, {}, extra FROM battles WHERE round_id = ?1 ORDER BY battle_no", BATTLE_COLS.map(|c| c.0).join(", "));
+        let mut battle_stmt = conn.prepare(&battle_sql)?;
+        let round_sql = format!("SELECT id, kind, round_no, {}, extra FROM rounds ORDER BY id", ROUND_COLS.map(|c| c.0).join(", "));
+        let mut round_stmt = conn.prepare(&round_sql)?;
+        let rounds = round_stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<i64>>(2)?, join_cols(r, 3, &ROUND_COLS)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut prev_rounds = Vec::new();
+        for (id, kind, round_no, mut r) in rounds {
+            let battles = battle_stmt.query_map([id], |b| Ok((b.get::<_, i64>(0)?, join_cols(b, 1, &BATTLE_COLS)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut fights = Vec::new();
+            for (bn, mut b) in battles {
+                let events = events_stmt.query_map(params![id, bn], |e| e.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                b.insert(String::from("other_events"), json!(events));
+                fights.push(Value::Object(b));
+            }
+            if let Some(n) = round_no { // only rounds with a log have a round number
+                let log = r.entry("log").or_insert_with(|| json!({}));
+                log["fights"] = Value::Array(fights);
+                log["round_no"] = json!(n);
+            }
+            prev_rounds.push(if kind.is_empty() {
+                Value::Object(r)
+            }
+            else {
+                json!({ kind: r })
+            });
+        }
+        game.insert(String::from("prev_rounds"), Value::Array(prev_rounds));
+
+        Ok(Value::Object(game))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn read(&self, filename: &str) -> Result<Value, GameError> {
+        if !Path::new(filename).exists() { // opening would create it
+            return Err(GameError::NotFound(format!("file read error for file {}", filename)))
+        }
+        let conn = match Connection::open(filename) {
+            Ok(c) => c,
+            Err(e) => return Err(GameError::Io(format!("failed to open database {} ({})", filename, e)))
+        };
+        match Self::read_inner(&conn) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(GameError::Parse(format!("database read error for file {} ({})", filename, e))) // not a save, or a broken one
+        }
+    }
+
+    fn write(&self, filename: &str, game: &Value) -> Result<(), GameError> {
+        let mut conn = match Connection::open(filename) {
+            Ok(c) => c,
+            Err(e) => return Err(GameError::Io(format!("failed to open database {} ({})", filename, e)))
+        };
+        match Self::write_inner(&mut conn, game) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GameError::Io(format!("failed to write database {} ({})", filename, e)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_sqlite_round_trip() {
+        let game = json!({
+            "season_name": "test",
+            "num_rounds": 1,
+            "next_round": null,
+            "fighters": [
+                {"name": "bob", "owner": "al", "class": "Cleric", "strength": 4, "speed": -1, "skill": 4, "dead": false, "rating": 2,
+                 "kills": 0, "battles_won": 1, "battles_fought": 1, "unspent_points": 1, "pre_matched": false, "something_new": [1, 2]}
+            ],
+            "prev_rounds": [
+                {"Standard": {"matchups": [[0, 1]], "sitting_out": null, "arena": "Hills", "modifier": "Rulebook", "run": true,
+                 "log": {"count": 0, "round_no": 1, "fights": [
+                    {"fighter_1": 0, "rolls_1": [1, 2, 3], "injury_1": null, "fighter_2": 1, "rolls_2": [3, 2, 1], "injury_2": 5,
+                     "points": 1, "result": "F1Win", "other_events": ["f1 wins instantly"]}
+                 ]}}}
+            ]
+        });
+        let path = std::env::temp_dir().join(format!("gw_storage_test_{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        SqliteStorage.write(path, &game).unwrap();
+        SqliteStorage.write(path, &game).unwrap(); // writing over an existing save
+        let read = SqliteStorage.read(path).unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(read, game);
+    }
+
+    #[test]
+    fn test_sqlite_writes_only_changes() {
+        let mut game = json!({
+            "season_name": "test",
+            "fighters": [{"name": "a", "kills": 0}, {"name": "b", "kills": 0}, {"name": "c", "kills": 0}],
+            "prev_rounds": [
+                {"Standard": {"matchups": [[0, 1]], "log": {"round_no": 1, "fights": [{"fighter_1": 0, "fighter_2": 1, "other_events": ["x", "y"]}]}}}
+            ]
+        });
+        let mut conn = Connection::open_in_memory().unwrap();
+        SqliteStorage::write_inner(&mut conn, &game).unwrap();
+        let before = conn.total_changes();
+        SqliteStorage::write_inner(&mut conn, &game).unwrap();
+        assert_eq!(conn.total_changes(), before); // nothing changed, nothing written
+
+        game["fighters"][1]["kills"] = json!(1);
+        SqliteStorage::write_inner(&mut conn, &game).unwrap();
+        assert_eq!(conn.total_changes(), before + 1);
+
+        game["fighters"].as_array_mut().unwrap().pop();
+        game["prev_rounds"] = json!([]);
+        SqliteStorage::write_inner(&mut conn, &game).unwrap();
+        assert_eq!(conn.total_changes(), before + 6); // the fighter, the round, its battle and both events
+        let read = SqliteStorage::read_inner(&conn).unwrap();
+        assert_eq!((read["fighters"].as_array().unwrap().len(), read["fighters"][1]["kills"].clone()), (2, json!(1)));
+        assert_eq!(read["prev_rounds"], json!([]));
+    }
+}
@@ -0,0 +1,297 @@
+use serde_json::{Value, json};
+use std::fs;
+use std::sync::LazyLock;
+
+use crate::game::GameState;
+use crate::report::RoundReport;
+use crate::output;
+use crate::utils::{fmt_vec, fmt_option};
+use crate::error::GameError;
+
+// user templates for rounds, fighter cards and standings. a small subset of mustache:
+// {{name}} and {{a.b}} fill in a value, {{.}} is the current item, {{#x}}...{{/x}} repeats for each item of a list (or once if x is
+// true/filled in), {{^x}}...{{/x}} only shows if x is false/empty/missing, and {{! ...}} is a comment.
+// a line with nothing but a section tag on it doesn't leave a blank line behind
+
+pub const DEFAULT_FIGHTER_CARD: &str = "{{name}} ({{class}}, owned by {{owner}}){{#dead}} - dead{{/dead}}
+strength {{strength}}, speed {{speed}}, skill {{skill}} (total {{total}}{{#unspent_points}}, {{unspent_points}} unspent{{/unspent_points}})
+rating {{rating}}, {{kills}} kills, won {{battles_won}} of {{battles_fought}}";
+
+static FIGHTER_CARD: LazyLock<Template> = LazyLock::new(|| Template::parse(DEFAULT_FIGHTER_CARD).expect("the built in fighter card should parse")); // parsed the first time it's needed
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(String),
+    Section { name: String, inverted: bool, children: Vec<Node> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Text(String),
+    Tag(char, String), // kind ('#', '^', '/', '!' or ' ' for a plain value) and name
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()))
+        }
+        let end = match rest[start..].find("}}") {
+            Some(e) => start + e,
+            None => return Err(String::from("unclosed {{"))
+        };
+        let tag = rest[start + 2..end].trim();
+        let (kind, name) = match tag.chars().next() {
+            Some(c @ ('#' | '^' | '/' | '!')) => (c, tag[1..].trim().to_string()),
+            _ => (' ', tag.to_string())
+        };
+        if name.is_empty() && kind != '!' {
+            return Err(String::from("empty {{}}"))
+        }
+        tokens.push(Token::Tag(kind, name));
+        rest = &rest[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()))
+    }
+    Ok(tokens)
+}
+
+fn strip_standalone(tokens: &mut [Token]) { // {{#x}} alone on its line takes the whole line with it, like mustache
+    let mut standalone = Vec::new(); // worked out before changing anything, so two in a row both count
+    for i in 0..tokens.len() {
+        if !matches!(&tokens[i], Token::Tag('#' | '^' | '/' | '!', _)) {
+            continue
+        }
+        let before_ok = match i.checked_sub(1).map(|j| &tokens[j]) {
+            None => true,
+            Some(Token::Text(t)) => {
+                let tail = t.rsplit('\n').next().unwrap_or("");
+                tail.trim().is_empty() && (t.contains('\n') || i == 1)
+            }
+            Some(_) => false
+        };
+        let after_ok = match tokens.get(i + 1) {
+            None => true,
+            Some(Token::Text(t)) => {
+                let head = t.split('\n').next().unwrap_or("");
+                head.trim().is_empty() && (t.contains('\n') || i + 2 == tokens.len())
+            }
+            Some(_) => false
+        };
+        if before_ok && after_ok {
+            standalone.push(i)
+        }
+    }
+    for i in standalone {
+        if let Some(Token::Text(t)) = i.checked_sub(1).map(|j| &mut tokens[j]) {
+            let keep = t.rfind('\n').map(|n| n + 1).unwrap_or(0);
+            t.truncate(keep)
+        }
+        if let Some(Token::Text(t)) = tokens.get_mut(i + 1) {
+            *t = match t.find('\n') {
+                Some(n) => t[n + 1..].to_string(),
+                None => String::new()
+            }
+        }
+    }
+}
+
+fn build(tokens: &mut std::vec::IntoIter<Token>, open: Option<&str>) -> Result<Vec<Node>, String> {
+    let mut nodes = Vec::new();
+    while let Some(t) = tokens.next() {
+        match t {
+            Token::Text(s) if s.is_empty() => {}
+            Token::Text(s) => nodes.push(Node::Text(s)),
+            Token::Tag('!', _) => {}
+            Token::Tag(' ', name) => nodes.push(Node::Var(name)),
+            Token::Tag('/', name) => {
+                return match open {
+                    Some(o) if o == name => Ok(nodes),
+                    Some(o) => Err(format!("{{{{/{}}}}} closes {{{{#{}}}}}", name, o)),
+                    None => Err(format!("{{{{/{}}}}} was never opened", name))
+                }
+            }
+            Token::Tag(kind, name) => {
+                let children = build(tokens, Some(&name))?;
+                nodes.push(Node::Section { name, inverted: kind == '^', children })
+            }
+        }
+    }
+    match open {
+        Some(o) => Err(format!("{{{{#{}}}}} is never closed", o)),
+        None => Ok(nodes)
+    }
+}
+
+fn lookup<'a>(name: &str, stack: &[&'a Value]) -> Option<&'a Value> {
+    if name == "." {
+        return stack.last().copied()
+    }
+    let mut parts = name.split('.');
+    let first = parts.next()?;
+    let mut v = stack.iter().rev().find_map(|c| c.get(first))?; // innermost first, so a matchup's fields hide the round's
+    for p in parts {
+        v = v.get(p)?
+    }
+    Some(v)
+}
+
+fn truthy(v: Option<&Value>) -> bool {
+    match v {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(_) => true
+    }
+}
+
+fn render_nodes(nodes: &[Node], stack: &mut Vec<&Value>, out: &mut String) {
+    for n in nodes {
+        match n {
+            Node::Text(s) => out.push_str(s),
+            Node::Var(name) => match lookup(name, stack) {
+                Some(Value::String(s)) => out.push_str(s),
+                Some(Value::Null) | None => {}
+                Some(v) => out.push_str(&v.to_string())
+            }
+            Node::Section { name, inverted: true, children } => {
+                if !truthy(lookup(name, stack)) {
+                    render_nodes(children, stack, out)
+                }
+            }
+            Node::Section { name, inverted: false, children } => {
+                let v = lookup(name, stack);
+                if !truthy(v) {
+                    continue
+                }
+                match v {
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            stack.push(item);
+                            render_nodes(children, stack, out);
+                            stack.pop();
+                        }
+                    }
+                    Some(v) => {
+                        stack.push(v);
+                        render_nodes(children, stack, out);
+                        stack.pop();
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, String> {
+        let mut tokens = tokenize(text)?;
+        strip_standalone(&mut tokens);
+        let nodes = build(&mut tokens.into_iter(), None)?;
+        Ok(Template { nodes })
+    }
+
+    pub fn load(path: &str) -> Result<Template, GameError> {
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => return Err(GameError::Io(format!("could not read template {}: {}", path, e)))
+        };
+        Template::parse(&text).map_err(|e| GameError::Parse(format!("template {}: {}", path, e)))
+    }
+
+    pub fn render(&self, context: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![context], &mut out);
+        out
+    }
+}
+
+// what templates get to see. documented in the readme, so add to these rather than renaming things
+
+pub fn round_context(r: &RoundReport, season: &str) -> Value {
+    let matchups: Vec<Value> = r.matchups.iter().map(|m| {
+        let battle = m.battle.as_ref().map(|b| json!({
+            "rolls_1": fmt_vec(&b.rolls_1),
+            "rolls_2": fmt_vec(&b.rolls_2),
+            "injury_1": fmt_option(&b.injury_1),
+            "injury_2": fmt_option(&b.injury_2),
+            "events": b.other_events,
+            "winner": m.winner(),
+            "draw": m.winner().is_none()
+        }));
+        json!({ "fighter_1": m.fighter_1, "fighter_2": m.fighter_2, "battle": battle })
+    }).collect();
+    json!({
+        "season": season,
+        "round_no": r.round_no,
+        "arena": r.arena.to_string(),
+        "modifier": r.modifier.to_string(),
+        "run": r.run,
+        "matchups": matchups,
+        "sitting_out": r.sitting_out
+    })
+}
+
+pub fn fighter_context(game: &GameState, index: usize) -> Option<Value> {
+    let mut v = output::fighter(index, game.fighters.get(index)?);
+    v["season"] = json!(game.season_name);
+    Some(v)
+}
+
+pub fn fighter_card(game: &GameState, index: usize, template: Option<&Template>) -> Option<String> { // the built in card if there's no template
+    let ctx = fighter_context(game, index)?;
+    Some(match template {
+        Some(t) => t.render(&ctx),
+        None => FIGHTER_CARD.render(&ctx)
+    })
+}
+
+pub fn standings_context(game: &GameState) -> Value { // best first, with the dead at the bottom
+    let mut order: Vec<usize> = (0..game.fighters.len()).collect();
+    order.sort_by_key(|i| (game.fighters[*i].dead, -game.fighters[*i].rating, -game.fighters[*i].kills));
+    let fighters: Vec<Value> = order.iter().enumerate().map(|(rank, i)| {
+        let mut v = output::fighter(*i, &game.fighters[*i]);
+        v["rank"] = json!(rank + 1);
+        v
+    }).collect();
+    json!({ "season": game.season_name, "num_rounds": game.num_rounds, "fighters": fighters })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template() {
+        let t = Template::parse("round {{round_no}}\n{{#matchups}}\n* {{fighter_1.name}} vs {{fighter_2.name}}{{#battle}}: {{#winner}}{{name}} wins{{/winner}}{{#draw}}draw{{/draw}}{{/battle}}\n{{/matchups}}\n{{^sitting_out}}nobody sits out{{/sitting_out}}{{! not shown }}").unwrap();
+        let ctx = json!({
+            "round_no": 2,
+            "matchups": [
+                { "fighter_1": { "name": "a" }, "fighter_2": { "name": "b" }, "battle": { "winner": { "name": "b" }, "draw": false } },
+                { "fighter_1": { "name": "c" }, "fighter_2": { "name": "d" }, "battle": { "winner": null, "draw": true } }
+            ],
+            "sitting_out": null
+        });
+        assert_eq!(t.render(&ctx), "round 2\n* a vs b: b wins\n* c vs d: draw\nnobody sits out");
+        assert_eq!(Template::parse("{{#a}}\n{{#b}}\n  x\n{{/b}}\n{{/a}}\ny").unwrap().render(&json!({ "a": true, "b": [1] })), "  x\ny");
+        assert_eq!(Template::parse("{{#xs}}{{.}},{{/xs}}").unwrap().render(&json!({ "xs": [1, 2] })), "1,2,");
+        assert_eq!(Template::parse("{{missing}}|{{n}}").unwrap().render(&json!({ "n": 0 })), "|0");
+
+        assert!(Template::parse("{{#a}} no end").is_err());
+        assert!(Template::parse("{{#a}}{{/b}}").is_err());
+        assert!(Template::parse("{{/a}}").is_err());
+        assert!(Template::parse("{{a").is_err());
+        assert!(Template::parse(DEFAULT_FIGHTER_CARD).is_ok());
+    }
+}
@@ -0,0 +1,26 @@
+// fixtures shared by the tests, so every module doesn't build its own little season
+
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::GameState;
+use crate::fighter::{Fighter, Class};
+use crate::round::Pairing;
+
+pub fn season<S: AsRef<str>>(name: &str, fighters: &[S]) -> GameState { // everyone is an identical 3/3/3 mutant owned by o
+    let mut game = GameState::new_game(name);
+    for n in fighters {
+        game.add_fighter(Fighter::new(n.as_ref().to_string(), String::from("o"), Class::Mutant, 3, 3, 3))
+    }
+    game
+}
+
+pub fn play(game: &mut GameState, rounds: usize, rng: &mut ChaCha8Rng) { // schedules and runs that many rounds
+    for _ in 0..rounds {
+        game.new_round(None, None, Pairing::Random, rng).unwrap();
+        game.run_round(rng).unwrap();
+    }
+}
+
+pub fn temp_path(name: &str) -> String { // somewhere in the temp folder that no other test (or test run) uses
+    std::env::temp_dir().join(format!("gw_{}_{}", name, std::process::id())).to_string_lossy().to_string()
+}
@@ -0,0 +1,393 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier as Emphasis, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use rand::{thread_rng, Rng};
+
+use gladiator_war::game::GameState;
+use gladiator_war::fighter::Fighter;
+use gladiator_war::journal::Action;
+use gladiator_war::lock::SaveLock;
+use crate::cli::GwError;
+use gladiator_war::round::{Arena, Modifier, Pairing, Round, GameRound};
+use gladiator_war::utils::{split_args, timestamp};
+use crate::cli::{self, ProgramOptions};
+
+const SORTS: [&str; 7] = ["index", "name", "owner", "rating", "kills", "total", "points"];
+const KEYS: &str = "q quit  tab pane  up/down select  left/right battle  s sort  g generate  r run  c cancel  e edit  u undo";
+
+#[derive(PartialEq, Clone, Copy)]
+enum Pane {
+    Fighters,
+    Rounds,
+}
+
+enum Prompt {
+    Edit(usize), // fighter index
+    NewRound,
+}
+
+struct Dashboard {
+    game: GameState,
+    path: String,
+    name: String,
+    read_only: bool,
+    sort: usize, // index into SORTS
+    fighters: TableState,
+    rounds: ListState,
+    battle: usize,
+    pane: Pane,
+    prompt: Option<(Prompt, String)>,
+    message: String,
+    changed: bool, // anything saved, so the registry can be updated
+}
+
+impl Dashboard {
+    fn new(game: GameState, path: &str, name: &str, read_only: bool) -> Self {
+        let mut d = Dashboard {
+            rounds: ListState::default(),
+            fighters: TableState::default(),
+            path: path.to_string(),
+            name: name.to_string(),
+            read_only,
+            sort: 0,
+            battle: 0,
+            pane: Pane::Fighters,
+            prompt: None,
+            message: String::new(),
+            changed: false,
+            game
+        };
+        d.fix_selection();
+        d
+    }
+
+    fn sorted(&self) -> Vec<usize> { // fighter indexes in the current sort order
+        let mut order: Vec<usize> = (0..self.game.fighters.len()).collect();
+        let f = &self.game.fighters;
+        match SORTS[self.sort] {
+            "name" => order.sort_by_key(|i| f[*i].name.to_lowercase()),
+            "owner" => order.sort_by_key(|i| f[*i].owner.to_lowercase()),
+            "rating" => order.sort_by_key(|i| -f[*i].rating),
+            "kills" => order.sort_by_key(|i| -f[*i].kills),
+            "total" => order.sort_by_key(|i| -f[*i].total()),
+            "points" => order.sort_by_key(|i| -f[*i].unspent_points),
+            _ => {}
+        }
+        order
+    }
+
+    fn selected_fighter(&self) -> Option<usize> {
+        self.fighters.selected().and_then(|i| self.sorted().get(i).copied())
+    }
+
+    fn selected_round(&self) -> Option<&GameRound> {
+        match self.rounds.selected().and_then(|i| self.game.prev_rounds.get(i)) {
+            Some(Round::Standard(r)) => Some(r),
+            _ => None
+        }
+    }
+
+    fn fix_selection(&mut self) { // keeps selections in range after the game changes under them
+        let nf = self.game.fighters.len();
+        self.fighters.select(if nf == 0 { None } else { Some(self.fighters.selected().unwrap_or(0).min(nf - 1)) });
+        let nr = self.game.prev_rounds.len();
+        self.rounds.select(if nr == 0 { None } else { Some(self.rounds.selected().unwrap_or(nr - 1).min(nr - 1)) });
+        let nb = self.selected_round().map(|r| r.log.fights.len()).unwrap_or(0);
+        self.battle = self.battle.min(nb.saturating_sub(1));
+    }
+
+    fn act(&mut self, action: Action, po: &ProgramOptions) -> Result<(), GwError> { // errors here are the kind that should close the dashboard
+        if self.read_only {
+            self.message = String::from("this save is archived, so it can only be looked at");
+            return Ok(())
+        }
+        let description = action.to_string();
+        let rounds = self.game.prev_rounds.len();
+        match cli::apply(&mut self.game, action, po) {
+            Ok(o) if o.changed => {
+                let before = (self.game.generation, self.game.journal_len);
+                self.game.save_over(&self.path)?;
+                self.changed |= (self.game.generation, self.game.journal_len) != before;
+                self.message = format!("done: {}", description)
+            }
+            Ok(_) => self.message = format!("nothing to do: {}", description),
+            Err(e) => self.message = format!("error: {}", e)
+        }
+        if self.game.prev_rounds.len() > rounds { // show off the round that just ran
+            self.rounds.select(Some(self.game.prev_rounds.len() - 1));
+            self.battle = 0
+        }
+        self.fix_selection();
+        Ok(())
+    }
+
+    fn submit(&mut self, prompt: Prompt, text: &str, po: &ProgramOptions) -> Result<(), GwError> {
+        let words = match split_args(text) {
+            Ok(w) => w,
+            Err(e) => {
+                self.message = format!("error: {}", e);
+                return Ok(())
+            }
+        };
+        match prompt {
+            Prompt::Edit(fighter) => {
+                if words.is_empty() || words.len() % 2 != 0 {
+                    self.message = String::from("error: expected pairs of FIELD VALUE");
+                    return Ok(())
+                }
+                let changes = words.chunks(2).map(|c| (c[0].clone(), c[1].clone())).collect();
+                self.act(Action::EditFighter { fighter, changes, note: None }, po)
+            }
+            Prompt::NewRound => {
+                let mut arena = None;
+                let mut modifier = None;
+                let mut pairing = po.global_data.config.pairing;
+                for w in &words {
+                    if let Ok(a) = w.parse::<Arena>() {
+                        arena = Some(a)
+                    }
+                    else if let Ok(m) = w.parse::<Modifier>() {
+                        modifier = Some(m)
+                    }
+                    else if let Ok(p) = w.parse::<Pairing>() {
+                        pairing = p
+                    }
+                    else {
+                        self.message = format!("error: {} isn't an arena, a modifier or a pairing", w);
+                        return Ok(())
+                    }
+                }
+                self.act(Action::NewRound { arena, modifier, pairing, seed: thread_rng().gen(), round: None }, po)
+            }
+        }
+    }
+
+    fn key(&mut self, key: KeyCode, po: &ProgramOptions) -> Result<bool, GwError> { // returns false to quit
+        if let Some((prompt, mut text)) = self.prompt.take() {
+            match key {
+                KeyCode::Enter => self.submit(prompt, &text, po)?,
+                KeyCode::Esc => self.message = String::new(),
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.prompt = Some((prompt, text))
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.prompt = Some((prompt, text))
+                }
+                _ => self.prompt = Some((prompt, text))
+            }
+            return Ok(true)
+        }
+
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Tab => self.pane = if self.pane == Pane::Fighters { Pane::Rounds } else { Pane::Fighters },
+            KeyCode::Up | KeyCode::Down => {
+                let up = key == KeyCode::Up;
+                match (self.pane, up) {
+                    (Pane::Fighters, true) => self.fighters.select_previous(),
+                    (Pane::Fighters, false) => self.fighters.select_next(),
+                    (Pane::Rounds, true) => self.rounds.select_previous(),
+                    (Pane::Rounds, false) => self.rounds.select_next()
+                }
+                self.battle = 0
+            }
+            KeyCode::Left => self.battle = self.battle.saturating_sub(1),
+            KeyCode::Right => self.battle += 1,
+            KeyCode::Char('s') => self.sort = (self.sort + 1) % SORTS.len(),
+            KeyCode::Char('g') => {
+                self.message = String::from("arena and/or modifier, eg `crocpit blood`, or enter for random");
+                self.prompt = Some((Prompt::NewRound, String::new()))
+            }
+            KeyCode::Char('r') => self.act(Action::RunRound { seed: thread_rng().gen(), round: None }, po)?,
+            KeyCode::Char('c') => self.act(Action::CancelRound, po)?,
+            KeyCode::Char('u') => self.act(Action::Undo { n: 1 }, po)?,
+            KeyCode::Char('e') => match self.selected_fighter() {
+                Some(i) => {
+                    self.message = format!("pairs of FIELD VALUE, eg `rating 3 dead false`. fields: {}", Fighter::EDITABLE_FIELDS.join(", "));
+                    self.prompt = Some((Prompt::Edit(i), String::new()))
+                }
+                None => self.message = String::from("no fighter selected")
+            }
+            _ => {}
+        }
+        self.fix_selection();
+        Ok(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default().direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(10), Constraint::Length(2)])
+            .split(frame.area());
+        let cols = Layout::default().direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(rows[1]);
+        let left = Layout::default().direction(Direction::Vertical)
+            .constraints([Constraint::Min(6), Constraint::Length(12)])
+            .split(cols[0]);
+        let right = Layout::default().direction(Direction::Vertical)
+            .constraints([Constraint::Length(8), Constraint::Min(6), Constraint::Length(12)])
+            .split(cols[1]);
+
+        let archived = if self.read_only { "  (archived)" } else { "" };
+        let title = format!("{} ({}) - {} rounds played{}", self.game.season_name, self.name, self.game.num_rounds, archived);
+        frame.render_widget(Paragraph::new(title).style(Style::default().add_modifier(Emphasis::BOLD)), rows[0]);
+
+        let focused = |p: Pane| if self.pane == p { Style::default().fg(Color::Yellow) } else { Style::default() };
+        let highlight = Style::default().add_modifier(Emphasis::REVERSED);
+
+        // fighters
+        let header = Row::new(["#", "name", "owner", "class", "str", "spd", "skl", "pts", "total", "rating", "kills"]).style(Style::default().add_modifier(Emphasis::BOLD));
+        let table_rows: Vec<Row> = self.sorted().into_iter().map(|i| {
+            let f = &self.game.fighters[i];
+            let row = Row::new([i.to_string(), f.name.clone(), f.owner.clone(), f.class.to_string(), f.strength.to_string(), f.speed.to_string(), f.skill.to_string(),
+                f.unspent_points.to_string(), f.total().to_string(), f.rating.to_string(), f.kills.to_string()]);
+            if f.dead { row.style(Style::default().fg(Color::DarkGray)) } else { row }
+        }).collect();
+        let widths = [Constraint::Length(3), Constraint::Min(6), Constraint::Min(5), Constraint::Length(8), Constraint::Length(3), Constraint::Length(3),
+            Constraint::Length(3), Constraint::Length(3), Constraint::Length(5), Constraint::Length(6), Constraint::Length(5)];
+        let table = Table::new(table_rows, widths).header(header).row_highlight_style(highlight)
+            .block(Block::default().borders(Borders::ALL).border_style(focused(Pane::Fighters)).title(format!("fighters (sorted by {})", SORTS[self.sort])));
+        frame.render_stateful_widget(table, left[0], &mut self.fighters);
+
+        // scheduled round
+        let next = match self.game.next_round_report() {
+            Ok(Some(r)) => r.to_string(),
+            Ok(None) => String::from("no round scheduled"),
+            Err(e) => e.to_string()
+        };
+        frame.render_widget(Paragraph::new(next.replace('\t', "  ")).wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("scheduled round")), left[1]);
+
+        // round history
+        let items: Vec<ListItem> = self.game.prev_rounds.iter().map(|r| match r {
+            Round::Standard(r) => ListItem::new(format!("round {}: {}, {}", r.log.round_no, r.arena, r.modifier)),
+            Round::Boss(_) => ListItem::new("boss round")
+        }).collect();
+        let list = List::new(items).highlight_style(highlight)
+            .block(Block::default().borders(Borders::ALL).border_style(focused(Pane::Rounds)).title("round history"));
+        frame.render_stateful_widget(list, right[0], &mut self.rounds);
+
+        let selected = self.rounds.selected().and_then(|i| self.game.prev_rounds.get(i));
+        let text = match selected.map(|r| self.game.report_round(r)) {
+            Some(Ok(r)) => r.to_string(),
+            Some(Err(e)) => e.to_string(),
+            None => String::from("no rounds played yet")
+        };
+        frame.render_widget(Paragraph::new(text.replace('\t', "  ")).wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("round")), right[1]);
+
+        // battle detail
+        let report = selected.and_then(|r| self.game.report_round(r).ok());
+        let detail = match report.as_ref().filter(|r| r.run).and_then(|r| r.matchups.get(self.battle).map(|m| (r.matchups.len(), m))) {
+            Some((n, m)) => format!("battle {} of {}: {} VS {}\n{}", self.battle + 1, n, m.fighter_1.name, m.fighter_2.name, m.battle_text()),
+            None => String::from("no battle selected")
+        };
+        frame.render_widget(Paragraph::new(detail.replace('\t', "  ")).wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title("battle (left/right)")), right[2]);
+
+        // status line
+        let status = match &self.prompt {
+            Some((Prompt::Edit(i), text)) => format!("{}\nedit {}> {}", self.message, self.game.fighters[*i].name, text),
+            Some((Prompt::NewRound, text)) => format!("{}\nnew round> {}", self.message, text),
+            None => format!("{}\n{}", self.message, KEYS)
+        };
+        frame.render_widget(Paragraph::new(status), rows[2]);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal, po: &ProgramOptions) -> Result<(), GwError> {
+        loop {
+            if let Err(e) = terminal.draw(|f| self.draw(f)) {
+                return Err(GwError::Io(format!("could not draw: {}", e)))
+            }
+            match event::read() {
+                Ok(Event::Key(k)) if k.kind == KeyEventKind::Press => {
+                    if !self.key(k.code, po)? {
+                        return Ok(())
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => return Err(GwError::Io(format!("could not read input: {}", e)))
+            }
+        }
+    }
+}
+
+pub fn run_tui(po: &mut ProgramOptions, save_index: usize) -> Result<(), GwError> {
+    let entry = po.global_data.saves[save_index].clone();
+    let _lock = SaveLock::acquire(&entry.path)?;
+    let game = GameState::load_from_file(&entry.path).map_err(GwError::from)?;
+    let mut dash = Dashboard::new(game, &entry.path, &entry.name, entry.archived);
+
+    let verbosity = po.verbosity;
+    po.verbosity = -2; // anything printed would draw over the dashboard
+    let result = match ratatui::try_init() {
+        Ok(mut terminal) => {
+            let r = dash.run(&mut terminal, po);
+            ratatui::restore();
+            r
+        }
+        Err(e) => Err(GwError::Io(format!("could not start the dashboard: {}", e)))
+    };
+    po.verbosity = verbosity;
+
+    if dash.changed {
+        po.global_data.saves[save_index].last_modified = timestamp()
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{Terminal, backend::TestBackend};
+    use gladiator_war::fighter::Class;
+
+    #[test]
+    fn test_dashboard() {
+        let path = std::env::temp_dir().join(format!("gw_tui_test_{}.json", std::process::id())).to_string_lossy().to_string();
+        let mut game = GameState::new_game("tui test");
+        for (i, n) in ["alpha", "bravo", "charlie", "delta"].iter().enumerate() {
+            game.fighters.push(Fighter::new(n.to_string(), String::from("o"), Class::Mutant, 3, 3, i as i32))
+        }
+        game.save_to_file(&path).unwrap();
+        let po = ProgramOptions { verbosity: -2, ..ProgramOptions::default() };
+        let mut dash = Dashboard::new(game, &path, "tui-test", false);
+
+        dash.key(KeyCode::Char('g'), &po).unwrap();
+        for c in "crocpit".chars() {
+            dash.key(KeyCode::Char(c), &po).unwrap();
+        }
+        dash.key(KeyCode::Enter, &po).unwrap();
+        assert!(dash.game.next_round.is_some());
+        dash.key(KeyCode::Char('r'), &po).unwrap();
+        assert_eq!(dash.game.num_rounds, 1);
+        assert_eq!(dash.rounds.selected(), Some(0));
+
+        dash.key(KeyCode::Char('s'), &po).unwrap(); // by name, alpha first
+        dash.key(KeyCode::Char('e'), &po).unwrap();
+        for c in "owner zed".chars() {
+            dash.key(KeyCode::Char(c), &po).unwrap();
+        }
+        dash.key(KeyCode::Enter, &po).unwrap();
+        assert_eq!(dash.game.fighters[0].owner, "zed", "{}", dash.message);
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        terminal.draw(|f| dash.draw(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("charlie"));
+        assert!(screen.contains("crocodile pit"));
+        assert!(screen.contains("battle 1 of 2"));
+
+        assert!(!dash.key(KeyCode::Char('q'), &po).unwrap());
+        let saved = GameState::load_from_file(&path).unwrap();
+        assert_eq!(saved.num_rounds, 1);
+        let _ = std::fs::remove_file(&path);
+        for b in crate::backup::list_backups(&path) {
+            let _ = std::fs::remove_file(b);
+        }
+    }
+}
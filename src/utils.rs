@@ -2,10 +2,10 @@ use std::cmp::Ordering;
 use std::fs;
 use std::path::Path;
 use std::fmt;
-use std::io::{stdin, Write, stdout};
-
-use crate::global;
+use std::fs::File;
+use std::io::Write;
 
+#[allow(clippy::collapsible_match)]
 pub fn select_largest<T: std::cmp::PartialOrd>(a: T, b: T) -> T {
     match a.partial_cmp(&b) {
         None => a,
@@ -18,6 +18,7 @@ pub fn select_largest<T: std::cmp::PartialOrd>(a: T, b: T) -> T {
     }
 }
 
+#[allow(clippy::comparison_to_empty, clippy::op_ref)]
 pub fn get_non_repeating_filename(full_path: &str) -> Result<String, String> {
     let (mut folder_path, full_filename) = get_last(full_path, '/'); // split input into folder path and name
 
@@ -34,8 +35,7 @@ pub fn get_non_repeating_filename(full_path: &str) -> Result<String, String> {
 
     let (filename, extension) = get_last(full_filename, '.'); // split name from extension
 
-    for e in dir {
-        let entry_name = e.unwrap().file_name().into_string().unwrap(); // hope it doesnt error here
+    for entry_name in dir.flatten().filter_map(|e| e.file_name().into_string().ok()) { // skip anything unreadable or not utf-8, it can't clash with our name anyway
         if string_ends_with(&entry_name, extension) { // if extensions are the same, add it to the list to be checked
             files.push(entry_name)
         }
@@ -72,6 +72,35 @@ pub fn get_non_repeating_filename(full_path: &str) -> Result<String, String> {
     Ok(format!("{}/{}", folder_path, check_string))
 }
 
+pub fn write_atomic(filename: &str, contents: impl AsRef<[u8]>) -> Result<(), String> { // write to a temp file, sync, then rename over the real one
+    let (folder, name) = get_last(filename, '/');
+    let tmp_name = if folder.is_empty() && !filename.starts_with('/') {
+        format!(".{}.tmp", name)
+    }
+    else {
+        format!("{}/.{}.tmp", folder, name)
+    };
+
+    let result = (|| -> std::io::Result<()> {
+        let mut f = File::create(&tmp_name)?;
+        f.write_all(contents.as_ref())?;
+        f.sync_all()?; // make sure its actually on disk before we replace anything
+        fs::rename(&tmp_name, filename)?;
+        if let Ok(dir) = File::open(if folder.is_empty() { "." } else { folder }) {
+            let _ = dir.sync_all(); // persist the rename. not all platforms let you do this
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_name); // dont leave junk lying around
+            Err(format!("failed to write file {} ({})", filename, e))
+        }
+    }
+}
+
 pub fn get_last(s: &str, delim: char) -> (&str, &str) { // (name, ext)
     //dbg!(s);
     //dbg!(delim);
@@ -96,30 +125,13 @@ fn string_ends_with(s: &str, e: &str) -> bool { // string, end
     &s[s.len() - e.len()..] == e // i got nothin on this one
 }
 
-#[derive(Default)]
-pub struct ProgramOptions {
-    pub global_data: global::GwGlobalData,
-    pub verbosity: i32,
-    pub logging: bool
-}
-
-impl ProgramOptions {
-    #[allow(dead_code)]
-    pub fn new(global_data: global::GwGlobalData) -> Self {
-        ProgramOptions {
-            global_data,
-            ..ProgramOptions::default()
-        }
-    }
-}
-
 pub fn fmt_vec<T: fmt::Display>(v: &Vec<T>) -> String {
     let mut ret = String::new();
     for i in v {
         ret.push_str(&format!("{}, ", i))
     }
 
-    String::from(&ret[..ret.len()-2]) // remove trailing comma
+    String::from(ret.strip_suffix(", ").unwrap_or(&ret)) // remove trailing comma. empty vecs used to panic here
 }
 
 pub fn fmt_option<T: fmt::Display>(o: &Option<T>) -> String {
@@ -141,32 +153,81 @@ pub fn fmt_vec_with_tabs<T: fmt::Display>(v: &Vec<T>, number_of_tabs: usize) ->
     ret
 }
 
-pub fn confirm(prompt: &str) -> bool {
-    loop {
-        print!("{} ", prompt);
-        let _ = stdout().flush();
-        let mut buf = String::new();
-        let _ = stdin().read_line(&mut buf);
-        //println!("{}", buf);
-        match buf.trim().to_lowercase().as_str() {
-            "yes"|"y" => {
-                return true
+pub fn timestamp() -> String { // human readable local time, used for audit trails and the like
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+pub fn split_args(line: &str) -> Result<Vec<String>, String> { // splits on spaces like a shell would, quotes keep words together
+    split_words(line, false)
+}
+
+pub fn split_script_line(line: &str) -> Result<Vec<String>, String> { // split_args, but # starts a comment
+    split_words(line, true)
+}
+
+fn split_words(line: &str, comments: bool) -> Result<Vec<String>, String> {
+    let mut ret = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true
             }
-            "no"|"n" => {
-                return false
+            (None, '#') if comments && !in_word => break, // comment, the rest of the line doesn't count
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    ret.push(std::mem::take(&mut word));
+                    in_word = false
+                }
             }
-            _ => {
-                println!("please select y/n")
+            (None, c) => {
+                word.push(c);
+                in_word = true
             }
         }
-
     }
+    if quote.is_some() {
+        return Err(String::from("unclosed quote"))
+    }
+    if in_word {
+        ret.push(word)
+    }
+    Ok(ret)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("add-fighter 'big bob' al mutant 3 3 3").unwrap(), vec!["add-fighter", "big bob", "al", "mutant", "3", "3", "3"]);
+        assert_eq!(split_args("  edit-fighter 0 --note \"\"  ").unwrap(), vec!["edit-fighter", "0", "--note", ""]);
+        assert_eq!(split_script_line("undo 2 # oops, and #3").unwrap(), vec!["undo", "2"]);
+        assert_eq!(split_args("edit-fighter 0 --note # 1 pick").unwrap(), vec!["edit-fighter", "0", "--note", "#", "1", "pick"]); // only scripts have comments
+        assert!(split_args("oops \"").is_err());
+    }
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join(format!("gw_atomic_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("save.json").to_string_lossy().to_string();
+        write_atomic(&path, "one").unwrap();
+        write_atomic(&path, "two").unwrap(); // replaces, doesn't append
+        let contents = fs::read_to_string(&path).unwrap();
+        let missing = write_atomic(&dir.join("nope/save.json").to_string_lossy(), "three");
+        let files: Vec<String> = fs::read_dir(&dir).unwrap().filter_map(|e| e.ok()).map(|e| e.file_name().to_string_lossy().to_string()).collect();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(contents, "two");
+        assert!(missing.is_err());
+        assert_eq!(files, vec!["save.json"]); // no temp files left behind
+    }
+    #[test]
     fn test_string_end() {
         assert!(string_ends_with("beans.file", "file"));
         assert!(!string_ends_with("beans.file", "wrong"));